@@ -0,0 +1,182 @@
+use particle::Particle;
+use physic_property::{PhysicProperty, Parameter};
+use point3::Point3;
+use rectangle::Rectangle;
+use vec3::Vec3;
+use drawable::{Drawable, DrawList, DrawCommand, Rgba};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Zone {
+    // follow the path, particles closer than `radius` of it are pushed along
+    Tube { path: Vec<Point3>, radius: f64 },
+    Conveyor { area: Rectangle, direction: Vec3 },
+    // push from the entry to the exit and toward the axis of the funnel
+    Funnel { entry: Point3, exit: Point3, entry_width: f64, exit_width: f64 }
+}
+
+// Zone giving a directional boost to the particles inside it, the speed along
+// the direction is capped at `max_speed`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Accelerator {
+    pub zone: Zone,
+    pub strength: f64,
+    pub max_speed: f64
+}
+
+fn normalize(v: Vec3) -> Vec3 {
+    let l = v.length();
+    if l == 0.0 { v } else { v / l }
+}
+
+// parameter of the projection of p on [a, b] and distance to the segment
+fn project(p: &Point3, a: &Point3, b: &Point3) -> (f64, f64) {
+    let ab = Vec3::new(b.x - a.x, b.y - a.y, 0.0);
+    let ap = Vec3::new(p.x - a.x, p.y - a.y, 0.0);
+    let len2 = ab.squared_length();
+    let t = if len2 == 0.0 { 0.0 } else { (ap.x * ab.x + ap.y * ab.y) / len2 };
+    let tc = t.max(0.0).min(1.0);
+    let d = Vec3::new(ap.x - ab.x * tc, ap.y - ab.y * tc, 0.0).length();
+    (t, d)
+}
+
+impl Accelerator {
+    pub fn new(zone: Zone, strength: f64, max_speed: f64) -> Accelerator {
+        Accelerator {
+            zone: zone,
+            strength: strength,
+            max_speed: max_speed
+        }
+    }
+
+    // Direction of the boost at the position, None outside of the zone
+    pub fn direction_at(&self, p: &Point3) -> Option<Vec3> {
+        match self.zone {
+            Zone::Tube { ref path, radius } => {
+                let mut best: Option<(f64, Vec3)> = None;
+                for w in path.windows(2) {
+                    let (_, d) = project(p, &w[0], &w[1]);
+                    if d <= radius && best.map_or(true, |(bd, _)| d < bd) {
+                        best = Some((d, normalize(Vec3::new(w[1].x - w[0].x, w[1].y - w[0].y, 0.0))));
+                    }
+                }
+                best.map(|(_, dir)| dir)
+            }
+            Zone::Conveyor { ref area, direction } => {
+                if area.is_in_bound(p) { Some(normalize(direction)) } else { None }
+            }
+            Zone::Funnel { entry, exit, entry_width, exit_width } => {
+                let (t, d) = project(p, &entry, &exit);
+                if t < 0.0 || t > 1.0 {
+                    return None;
+                }
+                let half_width = (entry_width + (exit_width - entry_width) * t) / 2.0;
+                if d > half_width {
+                    return None;
+                }
+                let axis = normalize(Vec3::new(exit.x - entry.x, exit.y - entry.y, 0.0));
+                // point of the axis in front of the particle
+                let on_axis = Point3::new(entry.x + (exit.x - entry.x) * t,
+                                          entry.y + (exit.y - entry.y) * t,
+                                          p.z);
+                let to_axis = Vec3::new(on_axis.x - p.x, on_axis.y - p.y, 0.0);
+                let centering = if half_width > 0.0 { to_axis / half_width } else { to_axis };
+                Some(normalize(axis + centering))
+            }
+        }
+    }
+}
+
+pub fn draw_arrow(list: &mut DrawList, from: &Point3, dir: &Vec3, len: f64, color: Rgba) {
+    let tip = (from.x + dir.x * len, from.y + dir.y * len);
+    let back = (-dir.x * len * 0.3, -dir.y * len * 0.3);
+    let side = (-dir.y * len * 0.2, dir.x * len * 0.2);
+    list.line(from.x as i16, from.y as i16, tip.0 as i16, tip.1 as i16, color);
+    list.push(DrawCommand::FilledTrigon {
+        x1: tip.0 as i16, y1: tip.1 as i16,
+        x2: (tip.0 + back.0 + side.0) as i16, y2: (tip.1 + back.1 + side.1) as i16,
+        x3: (tip.0 + back.0 - side.0) as i16, y3: (tip.1 + back.1 - side.1) as i16,
+        color: color
+    });
+}
+
+impl Drawable for Accelerator {
+    fn draw(&self, list: &mut DrawList) {
+        let color = (255, 160, 0, 200);
+        match self.zone {
+            Zone::Tube { ref path, radius } => {
+                for w in path.windows(2) {
+                    list.thick_line(w[0].x as i16, w[0].y as i16, w[1].x as i16, w[1].y as i16,
+                                    (radius * 2.0) as u8, (255, 160, 0, 40));
+                    let mid = Point3::new((w[0].x + w[1].x) / 2.0, (w[0].y + w[1].y) / 2.0, 0.0);
+                    let dir = normalize(Vec3::new(w[1].x - w[0].x, w[1].y - w[0].y, 0.0));
+                    draw_arrow(list, &mid, &dir, radius.max(10.0), color);
+                }
+            }
+            Zone::Conveyor { ref area, direction } => {
+                let x1 = area.up_left_corner.x as i16;
+                let y1 = area.up_left_corner.y as i16;
+                let x2 = (area.up_left_corner.x + area.width) as i16;
+                let y2 = (area.up_left_corner.y + area.height) as i16;
+                list.filled_rectangle(x1, y1, x2, y2, (255, 160, 0, 40));
+                list.rectangle(x1, y1, x2, y2, color);
+                draw_arrow(list, &area.center(), &normalize(direction),
+                           area.width.min(area.height) / 2.0, color);
+            }
+            Zone::Funnel { entry, exit, entry_width, exit_width } => {
+                let axis = normalize(Vec3::new(exit.x - entry.x, exit.y - entry.y, 0.0));
+                let perp = Vec3::new(-axis.y, axis.x, 0.0);
+                let xs = vec![(entry.x + perp.x * entry_width / 2.0) as i16,
+                              (exit.x + perp.x * exit_width / 2.0) as i16,
+                              (exit.x - perp.x * exit_width / 2.0) as i16,
+                              (entry.x - perp.x * entry_width / 2.0) as i16];
+                let ys = vec![(entry.y + perp.y * entry_width / 2.0) as i16,
+                              (exit.y + perp.y * exit_width / 2.0) as i16,
+                              (exit.y - perp.y * exit_width / 2.0) as i16,
+                              (entry.y - perp.y * entry_width / 2.0) as i16];
+                list.filled_polygon(xs.clone(), ys.clone(), (255, 160, 0, 40));
+                list.polygon(xs, ys, color);
+                let len = Vec3::new(exit.x - entry.x, exit.y - entry.y, 0.0).length();
+                draw_arrow(list, &entry, &axis, len, color);
+            }
+        }
+    }
+}
+
+impl PhysicProperty for Accelerator {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
+        let mut tmp = p.clone();
+        if let Some(dir) = self.direction_at(&p.get_position()) {
+            let v = p.get_direction();
+            let speed = v.x * dir.x + v.y * dir.y + v.z * dir.z;
+            let boost = self.strength.min(self.max_speed - speed);
+            if boost > 0.0 {
+                tmp.apply_force(dir * boost);
+            }
+        }
+        tmp
+    }
+    fn as_drawable(&self) -> Option<&Drawable> {
+        Some(self)
+    }
+    fn name(&self) -> &str {
+        match self.zone {
+            Zone::Tube { .. } => "Accelerator tube",
+            Zone::Conveyor { .. } => "Conveyor",
+            Zone::Funnel { .. } => "Funnel"
+        }
+    }
+    fn contains(&self, p: &Point3) -> bool {
+        self.direction_at(p).is_some()
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("strength", self.strength, 0.0, 50.0, 0.5),
+             Parameter::with_description("max_speed", "max speed", self.max_speed, 0.0, 1000.0, 10.0)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "strength" => self.strength = value,
+            "max_speed" => self.max_speed = value,
+            _ => {}
+        }
+    }
+}
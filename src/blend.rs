@@ -0,0 +1,44 @@
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BlendMode {
+    Alpha,
+    Additive,
+    Multiply,
+    Screen
+}
+
+impl Default for BlendMode {
+    fn default() -> BlendMode {
+        BlendMode::Alpha
+    }
+}
+
+impl BlendMode {
+    // Name used by the command line and the scenes
+    pub fn from_name(name: &str) -> Option<BlendMode> {
+        match name {
+            "alpha" => Some(BlendMode::Alpha),
+            "additive" => Some(BlendMode::Additive),
+            "multiply" => Some(BlendMode::Multiply),
+            "screen" => Some(BlendMode::Screen),
+            _ => None
+        }
+    }
+}
+
+pub(crate) fn mix(a: u8, b: u8, alpha: u8) -> u8 {
+    ((a as u32 * (255 - alpha as u32) + b as u32 * alpha as u32) / 255) as u8
+}
+
+// Color of a destination pixel after drawing `src` on it
+pub fn blend_pixel(mode: BlendMode, dst: (u8, u8, u8), src: (u8, u8, u8, u8)) -> (u8, u8, u8) {
+    let (r, g, b, a) = src;
+    let f: &Fn(u8, u8) -> u8 = match mode {
+        BlendMode::Alpha => &|_, s| s,
+        BlendMode::Additive => &|d, s| ::std::cmp::min(255, d as u32 + s as u32) as u8,
+        BlendMode::Multiply => &|d, s| (d as u32 * s as u32 / 255) as u8,
+        BlendMode::Screen => &|d, s| (255 - (255 - d as u32) * (255 - s as u32) / 255) as u8
+    };
+    (mix(dst.0, f(dst.0, r), a),
+     mix(dst.1, f(dst.1, g), a),
+     mix(dst.2, f(dst.2, b), a))
+}
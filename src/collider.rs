@@ -0,0 +1,251 @@
+use particle::Particle;
+use point3::Point3;
+use vec3::Vec3;
+use drawable::{Drawable, DrawList};
+
+// Static obstacles, the collisions are computed in the xy plane
+#[derive(Debug, PartialEq, Clone)]
+pub enum Shape {
+    Circle { center: Point3, radius: f64 },
+    // rotated box, angle in radian around the center
+    Box { center: Point3, width: f64, height: f64, angle: f64 },
+    Segment { a: Point3, b: Point3 },
+    // convex polygon, the points can be in any order (clockwise or not)
+    Polygon { points: Vec<Point3> }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Collider {
+    pub shape: Shape,
+    pub restitution: f64, // 1 keep all the normal speed, 0 stop the particle
+    pub friction: f64 // fraction of the tangential speed lost at each contact
+}
+
+fn sub(a: &Point3, b: &Point3) -> Vec3 {
+    Vec3::new(a.x - b.x, a.y - b.y, 0.0)
+}
+
+fn dot2(a: &Vec3, b: &Vec3) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+fn closest_on_segment(p: &Point3, a: &Point3, b: &Point3) -> Point3 {
+    let ab = sub(b, a);
+    let len2 = dot2(&ab, &ab);
+    let t = if len2 == 0.0 { 0.0 } else { (dot2(&sub(p, a), &ab) / len2).max(0.0).min(1.0) };
+    Point3::new(a.x + ab.x * t, a.y + ab.y * t, p.z)
+}
+
+// Signed distance from p to the shape and the normal pointing toward the outside
+fn distance_polygon(p: &Point3, points: &[Point3]) -> (f64, Vec3) {
+    let n = points.len();
+    // orientation of the polygon, to get outward normals
+    let mut area = 0.0;
+    for i in 0..n {
+        let a = &points[i];
+        let b = &points[(i + 1) % n];
+        area += a.x * b.y - b.x * a.y;
+    }
+    let sign = if area >= 0.0 { 1.0 } else { -1.0 };
+    let mut inside = true;
+    let mut best_out = (::std::f64::MAX, Vec3::new(0.0, 0.0, 0.0));
+    let mut best_in = (::std::f64::MAX, Vec3::new(0.0, 0.0, 0.0));
+    for i in 0..n {
+        let a = &points[i];
+        let b = &points[(i + 1) % n];
+        let e = sub(b, a);
+        let len = e.length();
+        if len == 0.0 {
+            continue;
+        }
+        let normal = Vec3::new(e.y, -e.x, 0.0) * (sign / len);
+        let side = dot2(&sub(p, a), &normal);
+        if side > 0.0 {
+            inside = false;
+        }
+        if -side < best_in.0 {
+            best_in = (-side, normal);
+        }
+        let q = closest_on_segment(p, a, b);
+        let d = sub(p, &q);
+        let dl = d.length();
+        if dl < best_out.0 {
+            best_out = (dl, if dl > 0.0 { d / dl } else { normal });
+        }
+    }
+    if inside {
+        (-best_in.0, best_in.1)
+    } else {
+        best_out
+    }
+}
+
+impl Shape {
+    pub fn box_corners(center: &Point3, width: f64, height: f64, angle: f64) -> Vec<Point3> {
+        let (s, c) = angle.sin_cos();
+        let hw = width / 2.0;
+        let hh = height / 2.0;
+        [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)].iter()
+            .map(|&(x, y)| Point3::new(center.x + x * c - y * s, center.y + x * s + y * c, center.z))
+            .collect()
+    }
+
+    pub fn signed_distance(&self, p: &Point3) -> (f64, Vec3) {
+        match *self {
+            Shape::Circle { center, radius } => {
+                let d = sub(p, &center);
+                let l = d.length();
+                if l == 0.0 {
+                    (-radius, Vec3::new(0.0, -1.0, 0.0))
+                } else {
+                    (l - radius, d / l)
+                }
+            }
+            Shape::Segment { a, b } => {
+                let q = closest_on_segment(p, &a, &b);
+                let d = sub(p, &q);
+                let l = d.length();
+                if l == 0.0 {
+                    let ab = sub(&b, &a);
+                    let n = Vec3::new(-ab.y, ab.x, 0.0);
+                    let nl = n.length();
+                    (0.0, if nl > 0.0 { n / nl } else { Vec3::new(0.0, -1.0, 0.0) })
+                } else {
+                    (l, d / l)
+                }
+            }
+            Shape::Box { center, width, height, angle } =>
+                distance_polygon(p, &Shape::box_corners(&center, width, height, angle)),
+            Shape::Polygon { ref points } => distance_polygon(p, points)
+        }
+    }
+}
+
+impl Collider {
+    pub fn new(s: Shape) -> Collider {
+        Collider {
+            shape: s,
+            restitution: 0.5,
+            friction: 0.1
+        }
+    }
+    pub fn with_restitution(mut self, r: f64) -> Collider {
+        self.restitution = r;
+        self
+    }
+    pub fn with_friction(mut self, f: f64) -> Collider {
+        self.friction = f;
+        self
+    }
+
+    // Push the particle out of the shape and make it bounce
+    pub fn collide(&self, p: &mut Particle) -> bool {
+        let pos = p.get_position();
+        let rad = p.get_radius() as f64;
+        let (dist, n) = self.shape.signed_distance(&pos);
+        if dist >= rad {
+            return false;
+        }
+        let push = rad - dist;
+        p.set_position(Point3::new(pos.x + n.x * push, pos.y + n.y * push, pos.z));
+        let dir = p.get_direction();
+        let vn = dot2(&dir, &n);
+        if vn < 0.0 {
+            let normal = n * vn;
+            let tangent = Vec3::new(dir.x - normal.x, dir.y - normal.y, 0.0);
+            let new_dir = tangent * (1.0 - self.friction) - normal * self.restitution;
+            p.apply_force(Vec3::new(new_dir.x - dir.x, new_dir.y - dir.y, 0.0));
+        }
+        true
+    }
+}
+
+fn draw_polygon(list: &mut DrawList, points: &[Point3]) {
+    let xs: Vec<i16> = points.iter().map(|p| p.x as i16).collect();
+    let ys: Vec<i16> = points.iter().map(|p| p.y as i16).collect();
+    list.filled_polygon(xs.clone(), ys.clone(), (120, 120, 120, 255));
+    list.polygon(xs, ys, (220, 220, 220, 255));
+}
+
+impl Drawable for Collider {
+    fn draw(&self, list: &mut DrawList) {
+        match self.shape {
+            Shape::Circle { center, radius } => {
+                list.filled_circle(center.x as i16, center.y as i16, radius as i16, (120, 120, 120, 255));
+                list.circle(center.x as i16, center.y as i16, radius as i16, (220, 220, 220, 255));
+            }
+            Shape::Box { center, width, height, angle } =>
+                draw_polygon(list, &Shape::box_corners(&center, width, height, angle)),
+            Shape::Segment { a, b } =>
+                list.thick_line(a.x as i16, a.y as i16, b.x as i16, b.y as i16, 3, (220, 220, 220, 255)),
+            Shape::Polygon { ref points } => draw_polygon(list, points)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    fn assert_distance(shape: &Shape, p: (f64, f64), dist: f64, normal: (f64, f64)) {
+        let (d, n) = shape.signed_distance(&Point3::new(p.0, p.1, 0.0));
+        assert_close(d, dist);
+        assert_close(n.x, normal.0);
+        assert_close(n.y, normal.1);
+    }
+
+    #[test]
+    fn circle() {
+        let c = Shape::Circle { center: Point3::new(10.0, 10.0, 0.0), radius: 5.0 };
+        assert_distance(&c, (13.0, 14.0), 0.0, (0.6, 0.8));
+        assert_distance(&c, (10.0, 20.0), 5.0, (0.0, 1.0));
+        assert_distance(&c, (8.0, 10.0), -3.0, (-1.0, 0.0));
+        assert_distance(&c, (10.0, 10.0), -5.0, (0.0, -1.0));
+    }
+
+    #[test]
+    fn segment() {
+        let s = Shape::Segment { a: Point3::new(0.0, 0.0, 0.0), b: Point3::new(10.0, 0.0, 0.0) };
+        assert_distance(&s, (5.0, 3.0), 3.0, (0.0, 1.0));
+        assert_distance(&s, (5.0, -2.0), 2.0, (0.0, -1.0));
+        assert_distance(&s, (-3.0, 4.0), 5.0, (-0.6, 0.8));
+        assert_distance(&s, (13.0, 0.0), 3.0, (1.0, 0.0));
+    }
+
+    #[test]
+    fn box_inside_and_outside() {
+        let b = Shape::Box { center: Point3::new(0.0, 0.0, 0.0), width: 4.0, height: 2.0, angle: 0.0 };
+        assert_distance(&b, (5.0, 0.0), 3.0, (1.0, 0.0));
+        assert_distance(&b, (0.0, -4.0), 3.0, (0.0, -1.0));
+        assert_distance(&b, (5.0, 5.0), 5.0, (0.6, 0.8));
+        assert_distance(&b, (1.5, 0.0), -0.5, (1.0, 0.0));
+        assert_distance(&b, (0.0, 0.5), -0.5, (0.0, 1.0));
+    }
+
+    #[test]
+    fn rotated_box() {
+        let b = Shape::Box { center: Point3::new(0.0, 0.0, 0.0), width: 4.0, height: 2.0,
+                             angle: ::std::f64::consts::PI / 2.0 };
+        // now 2 wide and 4 high
+        let (d, _) = b.signed_distance(&Point3::new(0.0, 3.0, 0.0));
+        assert_close(d, 1.0);
+        let (d, _) = b.signed_distance(&Point3::new(3.0, 0.0, 0.0));
+        assert_close(d, 2.0);
+    }
+
+    #[test]
+    fn polygon_in_any_order() {
+        let points = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(4.0, 0.0, 0.0), Point3::new(0.0, 4.0, 0.0)];
+        let mut reversed = points.clone();
+        reversed.reverse();
+        for pts in vec![points, reversed] {
+            let t = Shape::Polygon { points: pts };
+            assert_distance(&t, (1.0, -2.0), 2.0, (0.0, -1.0));
+            assert_distance(&t, (1.0, 0.5), -0.5, (0.0, -1.0));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use particle::{Particle, ParticleHandle};
+use point3::Point3;
+use vec3::Vec3;
+
+use std::collections::HashMap;
+
+// the particles move of direction / 10 by tick (see `Particle::update`)
+static MOVE_BY_DIRECTION : f64 = 0.1;
+
+// Links between particles, the particles are referenced by their handle so
+// the constraints survive the compaction of the particle list
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Constraint {
+    // keep the two particles at `length` of each other, stiffness in [0, 1]
+    Distance { a: ParticleHandle, b: ParticleHandle, length: f64, stiffness: f64 },
+    // damped spring, applied as a force once by tick
+    Spring { a: ParticleHandle, b: ParticleHandle, rest_length: f64, stiffness: f64, damping: f64 },
+    // attach a particle to a fixed point
+    Pin { a: ParticleHandle, position: Point3 }
+}
+
+fn offset(a: &Particle, b: &Particle) -> (Vec3, f64) {
+    let pa = a.get_position();
+    let pb = b.get_position();
+    let d = Vec3::new(pb.x - pa.x, pb.y - pa.y, pb.z - pa.z);
+    let l = d.length();
+    (d, l)
+}
+
+fn alive(particles: &[Particle], index: &HashMap<ParticleHandle, usize>, a: ParticleHandle) -> Option<usize> {
+    match index.get(&a) {
+        Some(&ia) if particles[ia].is_alive() => Some(ia),
+        _ => None
+    }
+}
+
+fn alive_pair(particles: &[Particle], index: &HashMap<ParticleHandle, usize>, a: ParticleHandle, b: ParticleHandle) -> Option<(usize, usize)> {
+    match (index.get(&a), index.get(&b)) {
+        (Some(&ia), Some(&ib)) if particles[ia].is_alive() && particles[ib].is_alive() => Some((ia, ib)),
+        _ => None
+    }
+}
+
+// Forces of the springs, computed once before the particles move
+pub fn apply_springs(constraints: &[Constraint], particles: &mut Vec<Particle>, index: &HashMap<ParticleHandle, usize>) {
+    for c in constraints {
+        if let Constraint::Spring { a, b, rest_length, stiffness, damping } = *c {
+            let (ia, ib) = match alive_pair(particles, index, a, b) {
+                Some(p) => p,
+                None => continue
+            };
+            let mut pa = particles[ia];
+            let mut pb = particles[ib];
+            let (d, l) = offset(&pa, &pb);
+            if l == 0.0 {
+                continue;
+            }
+            let n = d / l;
+            let rel = pb.get_direction() - pa.get_direction();
+            let rel_speed = rel.x * n.x + rel.y * n.y + rel.z * n.z;
+            let f = n * (stiffness * (l - rest_length) + damping * rel_speed);
+            pa.apply_force(f / pa.get_mass());
+            pb.apply_force(-f / pb.get_mass());
+            particles[ia] = pa;
+            particles[ib] = pb;
+        }
+    }
+}
+
+// Position based correction of the distance constraints and pins. The
+// velocities are corrected like the positions, otherwise the particles would
+// keep the speed the constraints prevent them to use.
+pub fn solve(constraints: &[Constraint], particles: &mut Vec<Particle>, index: &HashMap<ParticleHandle, usize>,
+             iterations: u32) {
+    let mut before: HashMap<usize, Point3> = HashMap::new();
+    for c in constraints {
+        let (a, b) = match *c {
+            Constraint::Distance { a, b, .. } => (a, Some(b)),
+            Constraint::Pin { a, .. } => (a, None),
+            Constraint::Spring { .. } => continue
+        };
+        for h in Some(a).into_iter().chain(b) {
+            if let Some(i) = alive(particles, index, h) {
+                before.insert(i, particles[i].get_position());
+            }
+        }
+    }
+    for _ in 0..iterations {
+        for c in constraints {
+            match *c {
+                Constraint::Distance { a, b, length, stiffness } => {
+                    let (ia, ib) = match alive_pair(particles, index, a, b) {
+                        Some(p) => p,
+                        None => continue
+                    };
+                    let mut pa = particles[ia];
+                    let mut pb = particles[ib];
+                    let (d, l) = offset(&pa, &pb);
+                    if l == 0.0 {
+                        continue;
+                    }
+                    // heavier particles move less
+                    let wa = 1.0 / pa.get_mass();
+                    let wb = 1.0 / pb.get_mass();
+                    let correction = d * ((l - length) / l * stiffness / (wa + wb));
+                    let mut pos = pa.get_position();
+                    pos.apply_vec(correction * wa);
+                    pa.set_position(pos);
+                    let mut pos = pb.get_position();
+                    pos.apply_vec(-correction * wb);
+                    pb.set_position(pos);
+                    particles[ia] = pa;
+                    particles[ib] = pb;
+                }
+                Constraint::Pin { a, position } => {
+                    if let Some(ia) = alive(particles, index, a) {
+                        particles[ia].set_position(position);
+                    }
+                }
+                Constraint::Spring { .. } => {}
+            }
+        }
+    }
+    for (i, start) in before {
+        let p = &mut particles[i];
+        let end = p.get_position();
+        p.apply_force(Vec3::new((end.x - start.x) / MOVE_BY_DIRECTION,
+                                (end.y - start.y) / MOVE_BY_DIRECTION,
+                                (end.z - start.z) / MOVE_BY_DIRECTION));
+    }
+}
@@ -0,0 +1,189 @@
+use accelerator::draw_arrow;
+use drawable::{Drawable, DrawList, Rgba};
+use font::OverlayFont;
+use particle::Particle;
+use point3::Point3;
+use vec3::Vec3;
+use rectangle::Rectangle;
+use world::World;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+
+// pixels by unit of direction (the particles move of direction / 10 by tick)
+static VELOCITY_SCALE : f64 = 0.2;
+// pixels by unit of direction change in one tick
+static FORCE_SCALE : f64 = 8.0;
+// size of the cells sampled to show the areas of effect
+static AREA_STEP : usize = 10;
+// extra distance from the particle edge where the mouse still hovers it
+static HOVER_MARGIN : f64 = 3.0;
+
+static AREA_COLORS : [Rgba; 6] = [(255, 0, 0, 40), (0, 255, 0, 40), (0, 120, 255, 40),
+                                  (255, 255, 0, 40), (255, 0, 255, 40), (0, 255, 255, 40)];
+
+// Cells of the view inside the area of each property, sampled with
+// `contains`. Kept until the properties, the bounds or the view change.
+struct AreaMask {
+    key: (u64, Rectangle, u32, u32),
+    cells: Vec<(usize, i16, i16)> // property, top left corner
+}
+
+impl AreaMask {
+    fn new(world: &World, width: u32, height: u32) -> AreaMask {
+        let mut cells = vec![];
+        for (i, prop) in world.properties.iter().enumerate() {
+            for y in (0..height as usize / AREA_STEP + 1).map(|i| i * AREA_STEP) {
+                for x in (0..width as usize / AREA_STEP + 1).map(|i| i * AREA_STEP) {
+                    let center = Point3::new((x + AREA_STEP / 2) as f64, (y + AREA_STEP / 2) as f64, 0.0);
+                    if prop.contains(&center) {
+                        cells.push((i, x as i16, y as i16));
+                    }
+                }
+            }
+        }
+        AreaMask {
+            key: AreaMask::key(world, width, height),
+            cells: cells
+        }
+    }
+
+    fn key(world: &World, width: u32, height: u32) -> (u64, Rectangle, u32, u32) {
+        (world.properties_version(), world.boundaries, width, height)
+    }
+}
+
+// Toggleable overlays to see what acts on the particles:
+// F1 velocity arrows, F2 net force arrows, F3 areas of effect of the
+// properties, F4 id of the particle under the mouse.
+// Drawn in the window only, the exported frames do not have them. The forces
+// are recorded by the world while `forces` is set, see `World::record_forces`.
+pub struct DebugView {
+    pub velocity: bool,
+    pub forces: bool,
+    pub areas: bool,
+    pub ids: bool,
+    mouse: (i32, i32),
+    area_mask: Option<AreaMask>
+}
+
+impl DebugView {
+    pub fn new() -> DebugView {
+        DebugView {
+            velocity: false,
+            forces: false,
+            areas: false,
+            ids: false,
+            mouse: (-1, -1),
+            area_mask: None
+        }
+    }
+
+    // Return true if the event was used by the debug view
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::KeyDown {keycode: Some(Keycode::F1), ..} => self.velocity = !self.velocity,
+            Event::KeyDown {keycode: Some(Keycode::F2), ..} => self.forces = !self.forces,
+            Event::KeyDown {keycode: Some(Keycode::F3), ..} => self.areas = !self.areas,
+            Event::KeyDown {keycode: Some(Keycode::F4), ..} => self.ids = !self.ids,
+            Event::MouseMotion {x, y, ..} => {
+                // others may need the mouse too
+                self.mouse = (x, y);
+                return false;
+            }
+            _ => return false
+        }
+        true
+    }
+
+    // Alive particle under the mouse, the closest one if several are
+    pub fn hovered<'a>(&self, world: &'a World) -> Option<&'a Particle> {
+        let (mx, my) = (self.mouse.0 as f64, self.mouse.1 as f64);
+        let mut best: Option<(&Particle, f64)> = None;
+        for p in world.particles.iter().filter(|p| p.is_alive()) {
+            let pos = p.get_position();
+            let d = ((pos.x - mx) * (pos.x - mx) + (pos.y - my) * (pos.y - my)).sqrt();
+            if d > p.get_radius() as f64 + HOVER_MARGIN {
+                continue;
+            }
+            if best.map_or(true, |(_, bd)| d < bd) {
+                best = Some((p, d));
+            }
+        }
+        best.map(|(p, _)| p)
+    }
+
+    // Overlay of a `width`x`height` view, to draw after the world
+    pub fn overlay<'a>(&'a mut self, world: &'a World, width: u32, height: u32) -> DebugOverlay<'a> {
+        let key = AreaMask::key(world, width, height);
+        if self.areas && self.area_mask.as_ref().map_or(true, |m| m.key != key) {
+            self.area_mask = Some(AreaMask::new(world, width, height));
+        }
+        DebugOverlay {
+            view: self,
+            world: world
+        }
+    }
+
+    // The text can not go through the draw list, it is drawn directly in the window
+    pub fn draw_label(&self, c: &mut Canvas<Window>, font: &OverlayFont, tc: &TextureCreator<WindowContext>,
+                      world: &World) {
+        if !self.ids {
+            return;
+        }
+        if let Some(p) = self.hovered(world) {
+            let d = p.get_direction();
+            font.draw(c, tc, &format!("#{} ({:.0}, {:.0})", p.handle().0, d.x, d.y),
+                      self.mouse.0 + 12, self.mouse.1 + 12, Color::RGBA(255, 255, 255, 255));
+        }
+    }
+}
+
+fn arrow(list: &mut DrawList, from: &Point3, v: &Vec3, scale: f64, color: Rgba) {
+    let len = (v.x * v.x + v.y * v.y).sqrt();
+    if len * scale < 1.0 {
+        return;
+    }
+    draw_arrow(list, from, &Vec3::new(v.x / len, v.y / len, 0.0), len * scale, color);
+}
+
+pub struct DebugOverlay<'a> {
+    view: &'a DebugView,
+    world: &'a World
+}
+
+impl<'a> Drawable for DebugOverlay<'a> {
+    fn draw(&self, list: &mut DrawList) {
+        let world = self.world;
+        if self.view.areas {
+            if let Some(ref mask) = self.view.area_mask {
+                let step = AREA_STEP as i16;
+                for &(i, x, y) in &mask.cells {
+                    list.filled_rectangle(x, y, x + step - 1, y + step - 1, AREA_COLORS[i % AREA_COLORS.len()]);
+                }
+            }
+        }
+        if self.view.velocity || self.view.forces {
+            for p in world.particles.iter().filter(|p| p.is_alive()) {
+                let pos = p.get_position();
+                if self.view.velocity {
+                    arrow(list, &pos, &p.get_direction(), VELOCITY_SCALE, (0, 255, 0, 200));
+                }
+                if self.view.forces {
+                    if let Some(force) = world.force(p.handle()) {
+                        arrow(list, &pos, &force, FORCE_SCALE, (255, 60, 60, 220));
+                    }
+                }
+            }
+        }
+        if self.view.ids {
+            if let Some(p) = self.view.hovered(world) {
+                let pos = p.get_position();
+                list.circle(pos.x as i16, pos.y as i16, p.get_radius() + 3, (255, 255, 255, 255));
+            }
+        }
+    }
+}
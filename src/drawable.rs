@@ -1,11 +1,135 @@
+use blend::BlendMode;
 
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-use sdl2::surface::Surface;
-// use sdl2::render::RenderTarget;
+pub type Rgba = (u8, u8, u8, u8);
 
+// Backend agnostic drawing primitives, executed by `sdl_render::render`
+#[derive(Debug, PartialEq, Clone)]
+pub enum DrawCommand {
+    Pixel { x: i16, y: i16, color: Rgba, blend: BlendMode },
+    Line { x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba, blend: BlendMode },
+    AaLine { x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba, blend: BlendMode },
+    ThickLine { x1: i16, y1: i16, x2: i16, y2: i16, width: u8, color: Rgba, blend: BlendMode },
+    Circle { x: i16, y: i16, radius: i16, color: Rgba },
+    FilledCircle { x: i16, y: i16, radius: i16, color: Rgba, blend: BlendMode },
+    Rectangle { x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba },
+    FilledRectangle { x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba },
+    Polygon { xs: Vec<i16>, ys: Vec<i16>, color: Rgba },
+    FilledPolygon { xs: Vec<i16>, ys: Vec<i16>, color: Rgba, blend: BlendMode },
+    FilledTrigon { x1: i16, y1: i16, x2: i16, y2: i16, x3: i16, y3: i16, color: Rgba },
+    // image loaded by main, centered on (x, y), angle in degree
+    Sprite { index: usize, x: f64, y: f64, scale: f64, angle: f64, color: Rgba, blend: BlendMode }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DrawList {
+    pub commands: Vec<DrawCommand>
+}
+
+impl DrawList {
+    pub fn new() -> DrawList {
+        DrawList {
+            commands: vec![]
+        }
+    }
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+    pub fn push(&mut self, c: DrawCommand) {
+        self.commands.push(c);
+    }
+    pub fn filled_circle(&mut self, x: i16, y: i16, radius: i16, color: Rgba) {
+        self.push(DrawCommand::FilledCircle { x: x, y: y, radius: radius, color: color, blend: BlendMode::Alpha });
+    }
+    pub fn circle(&mut self, x: i16, y: i16, radius: i16, color: Rgba) {
+        self.push(DrawCommand::Circle { x: x, y: y, radius: radius, color: color });
+    }
+    pub fn line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba) {
+        self.push(DrawCommand::Line { x1: x1, y1: y1, x2: x2, y2: y2, color: color, blend: BlendMode::Alpha });
+    }
+    pub fn thick_line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, width: u8, color: Rgba) {
+        self.push(DrawCommand::ThickLine { x1: x1, y1: y1, x2: x2, y2: y2, width: width, color: color,
+                                             blend: BlendMode::Alpha });
+    }
+    pub fn rectangle(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba) {
+        self.push(DrawCommand::Rectangle { x1: x1, y1: y1, x2: x2, y2: y2, color: color });
+    }
+    pub fn filled_rectangle(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, color: Rgba) {
+        self.push(DrawCommand::FilledRectangle { x1: x1, y1: y1, x2: x2, y2: y2, color: color });
+    }
+    pub fn polygon(&mut self, xs: Vec<i16>, ys: Vec<i16>, color: Rgba) {
+        self.push(DrawCommand::Polygon { xs: xs, ys: ys, color: color });
+    }
+    pub fn filled_polygon(&mut self, xs: Vec<i16>, ys: Vec<i16>, color: Rgba) {
+        self.push(DrawCommand::FilledPolygon { xs: xs, ys: ys, color: color, blend: BlendMode::Alpha });
+    }
+
+    // Resize everything, to render the world at another resolution. The radii
+    // and widths use the mean of the two factors.
+    pub fn scale(&mut self, sx: f64, sy: f64) {
+        let x = |v: &mut i16| *v = (*v as f64 * sx).round() as i16;
+        let y = |v: &mut i16| *v = (*v as f64 * sy).round() as i16;
+        let s = (sx + sy) / 2.0;
+        let r = |v: &mut i16| if *v > 0 {
+            *v = ::std::cmp::max(1, (*v as f64 * s).round() as i16);
+        };
+        for c in &mut self.commands {
+            match *c {
+                DrawCommand::Pixel { x: ref mut px, y: ref mut py, .. } => {
+                    x(px);
+                    y(py);
+                }
+                DrawCommand::Line { ref mut x1, ref mut y1, ref mut x2, ref mut y2, .. } |
+                DrawCommand::AaLine { ref mut x1, ref mut y1, ref mut x2, ref mut y2, .. } |
+                DrawCommand::Rectangle { ref mut x1, ref mut y1, ref mut x2, ref mut y2, .. } |
+                DrawCommand::FilledRectangle { ref mut x1, ref mut y1, ref mut x2, ref mut y2, .. } => {
+                    x(x1);
+                    y(y1);
+                    x(x2);
+                    y(y2);
+                }
+                DrawCommand::ThickLine { ref mut x1, ref mut y1, ref mut x2, ref mut y2, ref mut width, .. } => {
+                    x(x1);
+                    y(y1);
+                    x(x2);
+                    y(y2);
+                    *width = (*width as f64 * s).round().max(1.0).min(255.0) as u8;
+                }
+                DrawCommand::Circle { x: ref mut px, y: ref mut py, ref mut radius, .. } |
+                DrawCommand::FilledCircle { x: ref mut px, y: ref mut py, ref mut radius, .. } => {
+                    x(px);
+                    y(py);
+                    r(radius);
+                }
+                DrawCommand::Polygon { ref mut xs, ref mut ys, .. } |
+                DrawCommand::FilledPolygon { ref mut xs, ref mut ys, .. } => {
+                    for v in xs.iter_mut() {
+                        x(v);
+                    }
+                    for v in ys.iter_mut() {
+                        y(v);
+                    }
+                }
+                DrawCommand::FilledTrigon { ref mut x1, ref mut y1, ref mut x2, ref mut y2,
+                                            ref mut x3, ref mut y3, .. } => {
+                    x(x1);
+                    y(y1);
+                    x(x2);
+                    y(y2);
+                    x(x3);
+                    y(y3);
+                }
+                DrawCommand::Sprite { x: ref mut px, y: ref mut py, ref mut scale, .. } => {
+                    *px *= sx;
+                    *py *= sy;
+                    *scale *= s;
+                }
+            }
+        }
+    }
+}
+
+// Everything drawn goes through a DrawList, so the window and the offscreen
+// surfaces always get the same image
 pub trait Drawable {
-    //fn draw<T: RenderTarget>(&self, &mut canvas: Canvas<T>);
-    fn draw_window(&self, canvas: &mut Canvas<Window>);
-    fn draw_surface(&self, canvas: &mut Canvas<Surface>);
+    fn draw(&self, list: &mut DrawList);
 }
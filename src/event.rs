@@ -0,0 +1,22 @@
+use particle::ParticleHandle;
+use point3::Point3;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Wall {
+    Left,
+    Right,
+    Top,
+    Bottom
+}
+
+// Emitted by the world during `update` (and `add_particle` for the spawns)
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ParticleEvent {
+    Spawn { particle: ParticleHandle, position: Point3 },
+    Death { particle: ParticleHandle, position: Point3 },
+    Boundary { particle: ParticleHandle, position: Point3, wall: Wall },
+    // index of the collider in `World::colliders`, only when the contact begins
+    Collision { particle: ParticleHandle, position: Point3, collider: usize },
+    // index of the property in `World::properties`
+    EnterProperty { particle: ParticleHandle, position: Point3, property: usize }
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sdl2::image::SaveSurface;
+use sdl2::surface::Surface;
+
+// Numbered PNG files written in a directory, one every `every` frames. The
+// files are numbered without holes (frame_000000.png, frame_000001.png, ...)
+// so they can be given as is to an image sequence reader.
+pub struct FrameExporter {
+    dir: PathBuf,
+    every: u32,
+    frame: u32
+}
+
+impl FrameExporter {
+    pub fn new(dir: &Path, every: u32) -> Result<FrameExporter, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        Ok(FrameExporter {
+            dir: dir.to_path_buf(),
+            every: ::std::cmp::max(1, every),
+            frame: 0
+        })
+    }
+
+    // To call once by simulation frame, give the file to write when this
+    // frame must be exported
+    pub fn next_frame(&mut self) -> Option<PathBuf> {
+        let n = self.frame;
+        self.frame += 1;
+        if n % self.every != 0 {
+            return None;
+        }
+        Some(self.dir.join(format!("frame_{:06}.png", n / self.every)))
+    }
+}
+
+pub fn save_png(surface: &Surface, path: &Path) -> Result<(), String> {
+    surface.save(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn exporter(name: &str, every: u32) -> (FrameExporter, PathBuf) {
+        let dir = env::temp_dir().join(format!("particle_generator_{}", name));
+        (FrameExporter::new(&dir, every).unwrap(), dir)
+    }
+
+    #[test]
+    fn numbered_without_holes() {
+        let (mut e, dir) = exporter("every_3", 3);
+        let frames: Vec<Option<PathBuf>> = (0..7).map(|_| e.next_frame()).collect();
+        assert_eq!(frames, vec![Some(dir.join("frame_000000.png")), None, None,
+                                Some(dir.join("frame_000001.png")), None, None,
+                                Some(dir.join("frame_000002.png"))]);
+        assert!(dir.is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_frame_at_least() {
+        let (mut e, dir) = exporter("every_0", 0);
+        assert_eq!(e.next_frame(), Some(dir.join("frame_000000.png")));
+        assert_eq!(e.next_frame(), Some(dir.join("frame_000001.png")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
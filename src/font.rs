@@ -0,0 +1,179 @@
+use sdl_render::rgba_bytes_format;
+
+use std::path::Path;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{self, Canvas, TextureCreator};
+use sdl2::surface::Surface;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::{Window, WindowContext};
+
+// Tried in order when no font is given
+static SYSTEM_FONTS: &'static [&'static str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/liberation-sans/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/truetype/freefont/FreeSans.ttf",
+    "/usr/share/fonts/gnu-free/FreeSans.ttf",
+    "/usr/share/wesnoth/fonts/DejaVuSans.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf"
+];
+
+// 5x7 glyphs of the printable ASCII characters (from ' ' to '~'), one byte by
+// column, the least significant bit at the top
+static GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1c, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1c, 0x00], [0x08, 0x2a, 0x1c, 0x2a, 0x08], [0x08, 0x08, 0x3e, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31], [0x18, 0x14, 0x12, 0x7f, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3e],
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22],
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], [0x7f, 0x09, 0x09, 0x09, 0x01],
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41], [0x7f, 0x40, 0x40, 0x40, 0x40],
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e],
+    [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], [0x7f, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f],
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x3f, 0x40, 0x38, 0x40, 0x3f], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7f],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7e, 0x09, 0x01, 0x02], [0x0c, 0x52, 0x52, 0x52, 0x3e],
+    [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3d, 0x00],
+    [0x7f, 0x10, 0x28, 0x44, 0x00], [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78],
+    [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7c, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7c], [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], [0x1c, 0x20, 0x40, 0x20, 0x1c],
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c],
+    [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7f, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08]
+];
+
+// size of a bitmap glyph pixel on screen
+static BITMAP_SCALE: u32 = 2;
+
+// height of a line of text in the overlay panels
+pub static LINE_HEIGHT: i32 = 18;
+
+// Font of the overlay texts: a TrueType font when one can be loaded, else the
+// bitmap font built in the binary, so the overlay always works
+pub enum OverlayFont<'ttf> {
+    Ttf(Font<'ttf, 'static>),
+    Bitmap
+}
+
+impl<'ttf> OverlayFont<'ttf> {
+    // The font given on the command line, then the usual system fonts, then
+    // the bitmap font
+    pub fn load(ttf: Option<&'ttf Sdl2TtfContext>, path: Option<&Path>, size: u16) -> OverlayFont<'ttf> {
+        let ttf = match ttf {
+            Some(t) => t,
+            None => return OverlayFont::Bitmap
+        };
+        if let Some(p) = path {
+            match ttf.load_font(p, size) {
+                Ok(f) => return OverlayFont::bold(f),
+                Err(e) => eprintln!("{}: {}, looking for a system font", p.display(), e)
+            }
+        }
+        for p in SYSTEM_FONTS {
+            if let Ok(f) = ttf.load_font(Path::new(p), size) {
+                return OverlayFont::bold(f);
+            }
+        }
+        eprintln!("no TrueType font found, using the built-in bitmap font");
+        OverlayFont::Bitmap
+    }
+
+    fn bold(mut f: Font<'ttf, 'static>) -> OverlayFont<'ttf> {
+        f.set_style(::sdl2::ttf::STYLE_BOLD);
+        OverlayFont::Ttf(f)
+    }
+
+    pub fn render(&self, text: &str, color: Color) -> Surface<'static> {
+        match *self {
+            OverlayFont::Ttf(ref f) => f.render(text).blended(color).unwrap(),
+            OverlayFont::Bitmap => render_bitmap(text, color)
+        }
+    }
+
+    // Draw a text with its top left corner at (x, y)
+    pub fn draw(&self, c: &mut Canvas<Window>, tc: &TextureCreator<WindowContext>, text: &str,
+                x: i32, y: i32, color: Color) {
+        let surface = self.render(text, color);
+        let texture = tc.create_texture_from_surface(&surface).unwrap();
+        c.copy(&texture, None, Some(Rect::new(x, y, surface.width(), surface.height()))).unwrap();
+    }
+}
+
+// Lines of text over a background, on the top of the window, used by the
+// HUD and the inspector
+pub struct TextPanel {
+    pub x: i32,
+    pub width: i32
+}
+
+impl TextPanel {
+    pub fn background(&self, c: &mut Canvas<Window>, nb_lines: i32, color: Color) {
+        let previous = c.blend_mode();
+        c.set_blend_mode(render::BlendMode::Blend);
+        c.set_draw_color(color);
+        c.fill_rect(Rect::new(self.x, 0, self.width as u32, (nb_lines * LINE_HEIGHT + 4) as u32)).unwrap();
+        c.set_blend_mode(previous);
+    }
+
+    pub fn text(&self, c: &mut Canvas<Window>, font: &OverlayFont, tc: &TextureCreator<WindowContext>,
+                text: &str, line: i32, color: Color) {
+        font.draw(c, tc, text, self.x + 5, line * LINE_HEIGHT + 2, color);
+    }
+}
+
+fn render_bitmap(text: &str, color: Color) -> Surface<'static> {
+    let chars: Vec<char> = text.chars().collect();
+    let width = ::std::cmp::max(1, chars.len() as u32 * 6) * BITMAP_SCALE;
+    let height = 8 * BITMAP_SCALE;
+    let mut surface = Surface::new(width, height, rgba_bytes_format()).unwrap();
+    let pitch = surface.pitch() as usize;
+    surface.with_lock_mut(|pixels: &mut [u8]| {
+        for p in pixels.iter_mut() {
+            *p = 0;
+        }
+        for (i, c) in chars.iter().enumerate() {
+            let code = *c as usize;
+            // unknown characters are drawn as '?'
+            let glyph = if code >= 0x20 && code < 0x7f { &GLYPHS[code - 0x20] } else { &GLYPHS[31] };
+            for (col, bits) in glyph.iter().enumerate() {
+                for row in 0..7 {
+                    if bits & (1 << row) == 0 {
+                        continue;
+                    }
+                    let x0 = (i * 6 + col) * BITMAP_SCALE as usize;
+                    let y0 = row * BITMAP_SCALE as usize;
+                    for y in y0..y0 + BITMAP_SCALE as usize {
+                        for x in x0..x0 + BITMAP_SCALE as usize {
+                            let o = y * pitch + x * 4;
+                            pixels[o] = color.r;
+                            pixels[o + 1] = color.g;
+                            pixels[o + 2] = color.b;
+                            pixels[o + 3] = color.a;
+                        }
+                    }
+                }
+            }
+        }
+    });
+    surface
+}
@@ -0,0 +1,158 @@
+use drawable::{DrawList, Rgba};
+use font::{OverlayFont, TextPanel, LINE_HEIGHT};
+use sdl_render;
+use world::{self, World};
+use timing::FrameTimer;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+
+static HISTORY : usize = 120;
+static PANEL : TextPanel = TextPanel { x: 0, width: 290 };
+static GRAPH_X : i32 = 165;
+
+// Last values of a measure, drawn as a line scaled on the largest one
+struct Graph {
+    label: &'static str,
+    unit: &'static str,
+    color: Rgba,
+    values: VecDeque<f64>
+}
+
+impl Graph {
+    fn new(label: &'static str, unit: &'static str, color: Rgba) -> Graph {
+        Graph {
+            label: label,
+            unit: unit,
+            color: color,
+            values: VecDeque::new()
+        }
+    }
+
+    fn push(&mut self, v: f64) {
+        if self.values.len() >= HISTORY {
+            self.values.pop_front();
+        }
+        self.values.push_back(v);
+    }
+
+    fn text(&self) -> String {
+        format!("{}: {:.2}{}", self.label, self.values.back().cloned().unwrap_or(0.0), self.unit)
+    }
+
+    fn draw(&self, list: &mut DrawList, x: i32, y: i32, height: i32) {
+        let max = self.values.iter().cloned().fold(0.0, f64::max);
+        let scale = if max > 0.0 { (height - 1) as f64 / max } else { 0.0 };
+        let points: Vec<(i16, i16)> = self.values.iter().enumerate()
+            .map(|(i, v)| ((x + i as i32) as i16, (y + height - 1 - (v * scale) as i32) as i16))
+            .collect();
+        list.filled_rectangle(x as i16, y as i16, (x + HISTORY as i32 - 1) as i16, (y + height - 1) as i16,
+                              (40, 40, 40, 200));
+        for w in points.windows(2) {
+            list.line(w[0].0, w[0].1, w[1].0, w[1].1, self.color);
+        }
+    }
+}
+
+// Labeled statistics in the top left corner, H toggles it. The graphs show
+// the last HISTORY frames.
+pub struct Hud {
+    pub visible: bool,
+    // steps by frame when recording, the simulation does not follow the clock
+    pub recording: Option<u32>,
+    graphs: Vec<Graph>
+}
+
+// indices in `Hud::graphs`
+static FPS : usize = 0;
+static FRAME : usize = 1;
+static TICK : usize = 2;
+static PHYSICS : usize = 3;
+static RENDER : usize = 4;
+static ALIVE : usize = 5;
+static SPAWN : usize = 6;
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            visible: true,
+            recording: None,
+            graphs: vec![Graph::new("fps", "", (0, 255, 0, 255)),
+                         Graph::new("frame", " ms", (0, 200, 120, 255)),
+                         Graph::new("tick", " ms", (255, 200, 0, 255)),
+                         Graph::new("physics", " ms", (255, 120, 0, 255)),
+                         Graph::new("render", " ms", (0, 160, 255, 255)),
+                         Graph::new("alive", "", (255, 255, 255, 255)),
+                         Graph::new("spawn", "/tick", (255, 0, 255, 255))]
+        }
+    }
+
+    // Return true if the event was used by the HUD
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if let Event::KeyDown {keycode: Some(Keycode::H), ..} = *event {
+            self.visible = !self.visible;
+            return true;
+        }
+        false
+    }
+
+    // To call once by frame
+    pub fn record(&mut self, world: &World, timer: &FrameTimer, render_time: Duration) {
+        let stats = world.stats();
+        self.graphs[FPS].push(timer.fps());
+        self.graphs[FRAME].push(world::millis(timer.frame_time()));
+        self.graphs[TICK].push(stats.tick_ms);
+        self.graphs[PHYSICS].push(stats.physics_ms);
+        self.graphs[RENDER].push(world::millis(render_time));
+        self.graphs[ALIVE].push(stats.alive as f64);
+        self.graphs[SPAWN].push(stats.spawned_last_tick as f64);
+    }
+
+    pub fn draw(&self, c: &mut Canvas<Window>, font: &OverlayFont, tc: &TextureCreator<WindowContext>,
+                world: &World) {
+        if !self.visible {
+            return;
+        }
+        let stats = world.stats();
+        let mut lines = vec![];
+        lines.push((format!("particles: {} alive / {}", stats.alive, world.particles.len()),
+                    Color::RGBA(255, 255, 255, 255)));
+        if let Some(steps) = self.recording {
+            lines.push((format!("recording: {} ticks/frame, not real time", steps),
+                        Color::RGBA(255, 80, 80, 255)));
+        }
+        if !stats.property_ms.is_empty() {
+            lines.push(("property cost (cpu):".to_string(), Color::RGBA(200, 200, 200, 255)));
+            for (p, ms) in world.properties.iter().zip(stats.property_ms.iter()) {
+                lines.push((format!("  {}: {:.2} ms", p.name(), ms), Color::RGBA(200, 200, 200, 255)));
+            }
+        }
+        for p in &world.properties {
+            if let Some(n) = p.absorbed() {
+                lines.push((format!("{}: {} absorbed", p.name(), n), Color::RGBA(255, 0, 255, 255)));
+            }
+        }
+
+        let nb_lines = (self.graphs.len() + lines.len()) as i32;
+        PANEL.background(c, nb_lines, Color::RGBA(20, 20, 20, 180));
+        let mut list = DrawList::new();
+        for (i, g) in self.graphs.iter().enumerate() {
+            g.draw(&mut list, GRAPH_X, i as i32 * LINE_HEIGHT + 3, LINE_HEIGHT - 4);
+        }
+        sdl_render::render(&list, c, &mut []);
+
+        for (i, g) in self.graphs.iter().enumerate() {
+            let (r, gr, b, a) = g.color;
+            PANEL.text(c, font, tc, &g.text(), i as i32, Color::RGBA(r, gr, b, a));
+        }
+        for (i, &(ref text, color)) in lines.iter().enumerate() {
+            PANEL.text(c, font, tc, text, (self.graphs.len() + i) as i32, color);
+        }
+    }
+}
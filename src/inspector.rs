@@ -0,0 +1,204 @@
+use physic_property::{PhysicProperty, Parameter};
+use font::{OverlayFont, TextPanel, LINE_HEIGHT};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+
+static PANEL_WIDTH : i32 = 260;
+static SLIDER_WIDTH : i32 = 100;
+
+// Panel listing the physic properties of the world and allowing to tweak them
+// while the simulation is running.
+// I toggle the panel, Tab/Backspace select the property, Up/Down the parameter,
+// Left/Right change the value (faster with shift), the sliders can be dragged
+// with the mouse.
+pub struct Inspector {
+    pub visible: bool,
+    selected_property: usize,
+    selected_parameter: usize,
+    dragging: bool,
+    origin_x: i32
+}
+
+impl Inspector {
+    pub fn new(window_width: u32) -> Inspector {
+        Inspector {
+            visible: false,
+            selected_property: 0,
+            selected_parameter: 0,
+            dragging: false,
+            origin_x: window_width as i32 - PANEL_WIDTH
+        }
+    }
+
+    // The panel stays on the right side of the window
+    pub fn resize(&mut self, window_width: u32) {
+        self.origin_x = window_width as i32 - PANEL_WIDTH;
+    }
+
+    fn slider_rect(&self, line: i32) -> Rect {
+        Rect::new(self.origin_x + PANEL_WIDTH - SLIDER_WIDTH - 5,
+                  line * LINE_HEIGHT + 4,
+                  SLIDER_WIDTH as u32,
+                  (LINE_HEIGHT - 8) as u32)
+    }
+
+    // the selected property is drawn first, then one line by parameter
+    fn parameter_line(&self, param: usize) -> i32 {
+        1 + param as i32
+    }
+
+    // The properties can change behind the inspector (scene reload), keep the
+    // selection inside the current ones
+    fn clamp_selection(&mut self, props: &Vec<Box<PhysicProperty>>) {
+        if props.is_empty() {
+            self.selected_property = 0;
+            self.selected_parameter = 0;
+            self.dragging = false;
+            return;
+        }
+        if self.selected_property >= props.len() {
+            self.selected_property = props.len() - 1;
+            self.selected_parameter = 0;
+        }
+        let nb_params = props[self.selected_property].parameters().len();
+        if self.selected_parameter >= nb_params {
+            self.selected_parameter = if nb_params > 0 { nb_params - 1 } else { 0 };
+        }
+    }
+
+    fn set_from_slider(&self, props: &mut Vec<Box<PhysicProperty>>, x: i32) {
+        let prop = &mut props[self.selected_property];
+        let params = prop.parameters();
+        if let Some(param) = params.get(self.selected_parameter) {
+            let r = self.slider_rect(self.parameter_line(self.selected_parameter));
+            let ratio = ((x - r.x()) as f64 / r.width() as f64).max(0.0).min(1.0);
+            prop.set_parameter(param.name, param.min + ratio * (param.max - param.min));
+        }
+    }
+
+    fn change_selected(&self, props: &mut Vec<Box<PhysicProperty>>, steps: f64) {
+        let prop = &mut props[self.selected_property];
+        let params = prop.parameters();
+        if let Some(param) = params.get(self.selected_parameter) {
+            let value = (param.value + steps * param.step).max(param.min).min(param.max);
+            prop.set_parameter(param.name, value);
+        }
+    }
+
+    // Return true if the event was used by the inspector
+    pub fn handle_event(&mut self, event: &Event, props: &mut Vec<Box<PhysicProperty>>) -> bool {
+        if let Event::KeyDown {keycode: Some(Keycode::I), ..} = *event {
+            self.visible = !self.visible;
+            return true;
+        }
+        self.clamp_selection(props);
+        if !self.visible || props.is_empty() {
+            return false;
+        }
+        let nb_params = props[self.selected_property].parameters().len();
+        match *event {
+            Event::KeyDown {keycode: Some(Keycode::Tab), ..} => {
+                self.selected_property = (self.selected_property + 1) % props.len();
+                self.selected_parameter = 0;
+                true
+            }
+            Event::KeyDown {keycode: Some(Keycode::Backspace), ..} => {
+                self.selected_property = (self.selected_property + props.len() - 1) % props.len();
+                self.selected_parameter = 0;
+                true
+            }
+            Event::KeyDown {keycode: Some(Keycode::Down), ..} => {
+                if nb_params > 0 {
+                    self.selected_parameter = (self.selected_parameter + 1) % nb_params;
+                }
+                true
+            }
+            Event::KeyDown {keycode: Some(Keycode::Up), ..} => {
+                if nb_params > 0 {
+                    self.selected_parameter = (self.selected_parameter + nb_params - 1) % nb_params;
+                }
+                true
+            }
+            Event::KeyDown {keycode: Some(Keycode::Left), keymod, ..} => {
+                let steps = if keymod.intersects(::sdl2::keyboard::LSHIFTMOD | ::sdl2::keyboard::RSHIFTMOD) { 10.0 } else { 1.0 };
+                self.change_selected(props, -steps);
+                true
+            }
+            Event::KeyDown {keycode: Some(Keycode::Right), keymod, ..} => {
+                let steps = if keymod.intersects(::sdl2::keyboard::LSHIFTMOD | ::sdl2::keyboard::RSHIFTMOD) { 10.0 } else { 1.0 };
+                self.change_selected(props, steps);
+                true
+            }
+            Event::MouseButtonDown {mouse_btn: MouseButton::Left, x, y, ..} => {
+                for i in 0..nb_params {
+                    if self.slider_rect(self.parameter_line(i)).contains_point((x, y)) {
+                        self.selected_parameter = i;
+                        self.dragging = true;
+                        self.set_from_slider(props, x);
+                        return true;
+                    }
+                }
+                false
+            }
+            Event::MouseMotion {x, ..} if self.dragging => {
+                self.set_from_slider(props, x);
+                true
+            }
+            Event::MouseButtonUp {mouse_btn: MouseButton::Left, ..} if self.dragging => {
+                self.dragging = false;
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn panel(&self) -> TextPanel {
+        TextPanel {
+            x: self.origin_x,
+            width: PANEL_WIDTH
+        }
+    }
+
+    fn draw_parameter(&self, c: &mut Canvas<Window>, font: &OverlayFont, tc: &TextureCreator<WindowContext>,
+                      param: &Parameter, line: i32, selected: bool) {
+        let color = if selected { Color::RGBA(255, 255, 0, 255) } else { Color::RGBA(200, 200, 200, 255) };
+        self.panel().text(c, font, tc, &format!("{}: {:.2}", param.description, param.value), line, color);
+        let r = self.slider_rect(line);
+        c.set_draw_color(Color::RGBA(80, 80, 80, 255));
+        c.fill_rect(r).unwrap();
+        let ratio = if param.max > param.min {
+            ((param.value - param.min) / (param.max - param.min)).max(0.0).min(1.0)
+        } else {
+            0.0
+        };
+        let filled = (ratio * r.width() as f64) as u32;
+        if filled > 0 {
+            c.set_draw_color(color);
+            c.fill_rect(Rect::new(r.x(), r.y(), filled, r.height())).unwrap();
+        }
+    }
+
+    pub fn draw(&self, c: &mut Canvas<Window>, font: &OverlayFont, tc: &TextureCreator<WindowContext>,
+                props: &Vec<Box<PhysicProperty>>) {
+        if !self.visible || props.is_empty() {
+            return;
+        }
+        // the selection is only clamped on the next event
+        let selected = ::std::cmp::min(self.selected_property, props.len() - 1);
+        let prop = &props[selected];
+        let params = prop.parameters();
+        let panel = self.panel();
+        panel.background(c, params.len() as i32 + 1, Color::RGBA(20, 20, 20, 255));
+        panel.text(c, font, tc, &format!("[{}/{}] {}", selected + 1, props.len(), prop.name()),
+                   0, Color::RGBA(255, 255, 255, 255));
+        for (i, param) in params.iter().enumerate() {
+            self.draw_parameter(c, font, tc, param, self.parameter_line(i), i == self.selected_parameter);
+        }
+    }
+}
@@ -12,6 +12,27 @@ mod physic_property;
 mod world;
 mod rectangle;
 mod drawable;
+mod blend;
+mod collider;
+mod accelerator;
+mod constraint;
+mod event;
+mod sub_emitter;
+mod trail;
+mod sprite;
+mod renderer;
+mod sdl_render;
+mod software_render;
+mod noise;
+mod font;
+mod inspector;
+mod hud;
+mod debug;
+mod timing;
+mod scene;
+mod options;
+mod export;
+mod video;
 
 use vec3::Vec3;
 use point3::Point3;
@@ -19,34 +40,100 @@ use particle::ParticleBuilder;
 use physic_property::{Gravity, GravityWell, AirResistance, Wind, BigGravityWell};
 use rectangle::Rectangle;
 use world::World;
-use drawable::Drawable;
+use drawable::{Drawable, DrawList};
+use sprite::Sprites;
+use sdl_render::Offscreen;
+use font::OverlayFont;
+use inspector::Inspector;
+use hud::Hud;
+use debug::DebugView;
+use timing::FrameTimer;
+use scene::{Scene, SceneWatcher};
+use options::Options;
+use export::FrameExporter;
+use video::VideoSink;
 
 use std::boxed::Box;
 use std::env;
+use std::process;
+use std::time::Instant;
 
 use rand::{thread_rng, Rng};
 use sdl2::image::{INIT_PNG, INIT_JPG};
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
-use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
-use sdl2::render::SurfaceCanvas;
 use sdl2::surface::Surface;
-use sdl2::pixels::PixelFormatEnum;
-use sdl2::gfx::framerate::FPSManager;
-use sdl2::rect::Rect;
 
 
 // TODO add more flexibility (more complex gravity well, particle mass, ...)
 // TODO add collision ???
-// FIXME better drawing of physic properties
-// TODO other physic property (accelerator tube, ...)
+
+// Render the view (drawn in `list`, `width`x`height` pixels) at the export
+// resolution. It does not follow the window so the size of the exported frames
+// never changes, and `target` is only created for the first frame.
+fn render_output(list: &DrawList, width: u32, height: u32, options: &Options,
+                 target: &mut Option<Offscreen>, sprites: &Sprites) -> Surface<'static> {
+    let (ew, eh) = options.export_size.unwrap_or((options.width, options.height));
+    let target = target.get_or_insert_with(|| Offscreen::new(ew, eh, options.software, sprites));
+    if (ew, eh) == (width, height) {
+        target.render(list);
+    } else {
+        let mut scaled = list.clone();
+        scaled.scale(ew as f64 / width as f64, eh as f64 / height as f64);
+        target.render(&scaled);
+    }
+    target.to_surface()
+}
+
+// Send the frame to the PNG sequence and to the video, when they want it
+fn output_frame(list: &DrawList, width: u32, height: u32, options: &Options,
+                exporter: &mut Option<FrameExporter>, video: &mut Option<VideoSink>,
+                target: &mut Option<Offscreen>, sprites: &Sprites) {
+    let path = match *exporter {
+        Some(ref mut e) => e.next_frame(),
+        None => None
+    };
+    if path.is_none() && video.is_none() {
+        return;
+    }
+    let surface = render_output(list, width, height, options, target, sprites);
+    let result = path.map_or(Ok(()), |p| export::save_png(&surface, &p))
+        .and_then(|_| match *video {
+            Some(ref mut v) => v.write_frame(&sdl_render::surface_to_rgba(&surface)),
+            None => Ok(())
+        });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+// Wait for the video encoder before leaving
+fn finish_video(video: Option<VideoSink>) {
+    if let Some(v) = video {
+        if let Err(e) = v.finish() {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
     let args : Vec<String> = env::args().collect();
-    let width: u32  = args[1].parse().unwrap();
-    let height: u32 = args[2].parse().unwrap();
-    let parts_by_frame: u32 = args[3].parse().unwrap();
+    let options = match Options::from_args(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let width = options.width;
+    let height = options.height;
+    let parts_by_frame = options.parts_by_frame;
+    let render_mode = options.render_mode;
+    let blend_mode = options.blend_mode;
+    let trail_length = options.trail_length;
     let bound = Rectangle{
         up_left_corner: Point3::new(0.0,0.0,0.0),
         height: height as f64,
@@ -54,7 +141,7 @@ fn main() {
         depth: 0.0
     };
     let mut world = World::new(vec![Box::new(Gravity{}),
-                                    Box::new(Wind{}),
+                                    Box::new(Wind::new()),
                                     Box::new(AirResistance::new()),
                                     Box::new(GravityWell::new(Point3{
                                         x: bound.center().x - 200.0,
@@ -88,37 +175,151 @@ fn main() {
                                                             0.0))
                                        .with_radius(rng.gen_range(1, 10))
                                        .with_lifetime(rng.gen_range(50, 1000))
+                                       .with_render_mode(render_mode)
+                                       .with_blend_mode(blend_mode)
+                                       .with_trail(trail_length)
                                        .create()
                                }));
+    let mut scene_watcher = None;
+    if let Some(ref path) = options.scene {
+        match Scene::load(path, bound) {
+            Ok(scene) => world.load_scene(scene),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        scene_watcher = Some(SceneWatcher::new(path, bound));
+    }
     // SDL
+    let _image_context = sdl2::image::init(INIT_PNG | INIT_JPG).unwrap();
+    let sprites = match Sprites::load(&options.sprites) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let mut exporter = match options.export {
+        Some(ref dir) => match FrameExporter::new(dir, options.export_every) {
+            Ok(e) => Some(e),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => None
+    };
+    let mut draw_list = DrawList::new();
+    let mut output = None;
+
+    let (video_width, video_height) = options.export_size.unwrap_or((width, height));
+    let mut video = match options.video {
+        Some(ref v) if v == "-" => Some(VideoSink::stdout(video_width, video_height)),
+        Some(ref command) => match VideoSink::spawn(command, video_width, video_height,
+                                                    options.video_fps) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => None
+    };
+
+    if options.headless {
+        // no window, the frames are only produced for the exports
+        let mut frame = 0;
+        while options.frames.map_or(true, |n| frame < n) {
+            for _ in 0..options.steps_per_frame {
+                world.update();
+                for _ in 0..parts_by_frame {
+                    world.create_particle();
+                }
+            }
+            draw_list.clear();
+            world.draw(&mut draw_list);
+            output_frame(&draw_list, width, height, &options, &mut exporter, &mut video,
+                         &mut output, &sprites);
+            frame += 1;
+        }
+        finish_video(video);
+        return;
+    }
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
-    let _image_context = sdl2::image::init(INIT_PNG | INIT_JPG).unwrap();
     let window = video_subsystem.window("Particle generator", width, height)
         .position_centered()
+        .resizable()
         .build()
         .unwrap();
 
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+    let mut canvas = if options.vsync {
+        window.into_canvas().present_vsync().build().unwrap()
+    } else {
+        window.into_canvas().build().unwrap()
+    };
 
-    let fps_counter = FPSManager::new();
-    let ttf_context = sdl2::ttf::init().unwrap();
-    let mut font = ttf_context.load_font("/usr/share/wesnoth/fonts/DejaVuSans.ttf", 12).unwrap();
-    font.set_style(sdl2::ttf::STYLE_BOLD);
+    let ttf_context = sdl2::ttf::init().ok();
+    let font = OverlayFont::load(ttf_context.as_ref(), options.font.as_ref().map(|p| p.as_path()),
+                                 options.font_size);
     let texture_creator = canvas.texture_creator();
 
-    let mut cpt = 0;
+    let mut inspector = Inspector::new(width);
+    let mut hud = Hud::new();
+    let mut debug_view = DebugView::new();
+    // the view follows the window, one world unit by pixel
+    let mut offscreen = Offscreen::new(width, height, options.software, &sprites);
+    let mut view_texture = texture_creator.create_texture_streaming(offscreen.format(), width, height)
+        .unwrap();
+    let mut timer = FrameTimer::new(options.target_fps, options.tick_rate);
+    // the recordings need the same number of steps in every frame
+    let recording = exporter.is_some() || video.is_some();
+    if recording {
+        hud.recording = Some(options.steps_per_frame);
+    }
+    let mut frame = 0;
 
     'mainloop: loop {
-        let mut surface_canvas = SurfaceCanvas::from_surface(
-            Surface::new(1900, 1060, PixelFormatEnum::RGBA4444).unwrap()
-        ).unwrap();
+        if let Some(ref mut watcher) = scene_watcher {
+            match watcher.poll() {
+                Some(Ok(scene)) => world.load_scene(scene),
+                Some(Err(e)) => eprintln!("{}", e),
+                None => {}
+            }
+        }
 
         for event in sdl_context.event_pump().unwrap().poll_iter() {
+            if inspector.handle_event(&event, &mut world.properties) {
+                world.properties_changed();
+                continue;
+            }
+            if hud.handle_event(&event) || debug_view.handle_event(&event) {
+                continue;
+            }
             match event {
                 Event::Quit{..} |
                 Event::KeyDown {keycode: Option::Some(Keycode::Escape), ..} =>
                     break 'mainloop,
+                Event::Window {win_event: WindowEvent::Resized(w, h), ..} => {
+                    let (w, h) = (w.max(1) as u32, h.max(1) as u32);
+                    if (w, h) != offscreen.size() {
+                        offscreen = Offscreen::new(w, h, options.software, &sprites);
+                        view_texture = texture_creator.create_texture_streaming(offscreen.format(), w, h)
+                            .unwrap();
+                        inspector.resize(w);
+                        if options.resize_world {
+                            let mut b = world.boundaries;
+                            b.width = w as f64;
+                            b.height = h as f64;
+                            world.set_bounds(b);
+                            if let Some(ref mut watcher) = scene_watcher {
+                                watcher.set_default_bounds(b);
+                            }
+                        }
+                    }
+                }
                 Event::KeyDown {keycode: Option::Some(Keycode::Space), ..} => {
                     for _ in 0..1 {
                         world.create_particle();
@@ -127,58 +328,38 @@ fn main() {
                 _ => {}
             }
         }
-        if cpt == 0 {
+        if options.frames.map_or(false, |n| frame >= n) {
+            break 'mainloop;
+        }
+        let ticks = if recording { options.steps_per_frame } else { timer.ticks_due() };
+        world.profile_properties = hud.visible;
+        world.record_forces = debug_view.forces;
+        for _ in 0..ticks {
             world.update();
-        } else {
-            canvas.set_draw_color(Color::RGB(0, 0, 0));
-            canvas.clear();
-            surface_canvas.set_draw_color(Color::RGB(0, 0, 0));
-            surface_canvas.clear();
-            // Point where the particle are created
-            surface_canvas.filled_circle((bound.center().x) as i16,
-                                         bound.center().y as i16,
-                                         1,
-                                         (255, 255, 255, 255)
-            ).unwrap();
-            for p in &world.properties {
-                match p.as_drawable() {
-                    None => {},
-                    Some(d) => d.draw_surface(&mut surface_canvas)
-                }
-            }
-            for p in &world.particles {
-                if p.is_alive() {
-                    p.draw_surface(&mut surface_canvas);
-                }
-            }
-            world.boundaries.draw_surface(&mut surface_canvas);
-            let surface = font.render(&fps_counter.get_framerate().to_string())
-                .blended(Color::RGBA(0, 0, 255, 255)).unwrap();
-            let surface2 = font.render(&world.particles
-                                       .iter()
-                                       .filter(|p| p.is_alive())
-                                       .count()
-                                       .to_string())
-                .blended(Color::RGBA(0, 0, 255, 255)).unwrap();
-            let surface3 = font.render(&world.particles.len().to_string())
-                .blended(Color::RGBA(0, 0, 255, 255)).unwrap();
-            let texture = texture_creator.create_texture_from_surface(&surface).unwrap();
-            let texture2 = texture_creator.create_texture_from_surface(&surface2).unwrap();
-            let texture3 = texture_creator.create_texture_from_surface(&surface3).unwrap();
-            let texture_creator = canvas.texture_creator();
-            canvas.copy(&texture_creator.create_texture_from_surface(
-                surface_canvas.into_surface()
-            ).unwrap(),
-                        None,
-                        None).unwrap();
-            canvas.copy(&texture, None, Some(Rect::new(0, 0, 50, 50))).unwrap();
-            canvas.copy(&texture2, None, Some(Rect::new(0, 55, 50, 50))).unwrap();
-            canvas.copy(&texture3, None, Some(Rect::new(0, 110, 50, 50))).unwrap();
-            canvas.present();
             for _ in 0..parts_by_frame {
                 world.create_particle();
             }
         }
-        cpt = 1 - cpt;
+        frame += 1;
+
+        let render_start = Instant::now();
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        draw_list.clear();
+        world.draw(&mut draw_list);
+        let (view_width, view_height) = offscreen.size();
+        output_frame(&draw_list, view_width, view_height, &options, &mut exporter, &mut video,
+                     &mut output, &sprites);
+        debug_view.overlay(&world, view_width, view_height).draw(&mut draw_list);
+        offscreen.render(&draw_list);
+        offscreen.upload(&mut view_texture);
+        canvas.copy(&view_texture, None, None).unwrap();
+        hud.record(&world, &timer, render_start.elapsed());
+        hud.draw(&mut canvas, &font, &texture_creator, &world);
+        debug_view.draw_label(&mut canvas, &font, &texture_creator, &world);
+        inspector.draw(&mut canvas, &font, &texture_creator, &world.properties);
+        canvas.present();
+        timer.end_frame();
     }
+    finish_video(video);
 }
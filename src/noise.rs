@@ -0,0 +1,138 @@
+// Small deterministic noise functions, values in [0, 1]
+
+fn hash(i: i64, seed: u32) -> u32 {
+    let mut h = (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (seed as u64);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h as u32
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// 1D value noise, smoothly interpolated between random values at integer points
+pub fn value_noise(x: f64, seed: u32) -> f64 {
+    let i = x.floor();
+    let t = smooth(x - i);
+    let a = hash(i as i64, seed) as f64 / (::std::u32::MAX as f64);
+    let b = hash(i as i64 + 1, seed) as f64 / (::std::u32::MAX as f64);
+    a + (b - a) * t
+}
+
+// Improved Perlin noise (Ken Perlin, 2002), values roughly in [-1, 1]
+pub struct Perlin {
+    perm: Vec<u8>
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+impl Perlin {
+    pub fn new(seed: u32) -> Perlin {
+        let mut p: Vec<u8> = (0..256).map(|i| i as u8).collect();
+        // Fisher-Yates shuffle driven by the hash, so a seed always give the same field
+        for i in (1..256).rev() {
+            let j = (hash(i as i64, seed) as usize) % (i + 1);
+            p.swap(i, j);
+        }
+        let mut perm = p.clone();
+        perm.extend(p);
+        Perlin {
+            perm: perm
+        }
+    }
+
+    pub fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let xi = (x.floor() as i64 & 255) as usize;
+        let yi = (y.floor() as i64 & 255) as usize;
+        let zi = (z.floor() as i64 & 255) as usize;
+        let x = x - x.floor();
+        let y = y - y.floor();
+        let z = z - z.floor();
+        let u = fade(x);
+        let v = fade(y);
+        let w = fade(z);
+        let p = &self.perm;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+        lerp(w,
+             lerp(v,
+                  lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                  lerp(u, grad(p[ab], x, y - 1.0, z), grad(p[bb], x - 1.0, y - 1.0, z))),
+             lerp(v,
+                  lerp(u, grad(p[aa + 1], x, y, z - 1.0), grad(p[ba + 1], x - 1.0, y, z - 1.0)),
+                  lerp(u, grad(p[ab + 1], x, y - 1.0, z - 1.0), grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0))))
+    }
+
+    // Sum of `octaves` layers of noise, each one with twice the frequency and
+    // half the amplitude of the previous one
+    pub fn fbm(&self, x: f64, y: f64, z: f64, octaves: u32) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut total = 0.0;
+        for _ in 0..octaves.max(1) {
+            sum += amplitude * self.noise(x * frequency, y * frequency, z * frequency);
+            total += amplitude;
+            amplitude /= 2.0;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_noise_is_deterministic_and_in_range() {
+        for i in 0..100 {
+            let x = i as f64 * 0.37 - 10.0;
+            let v = value_noise(x, 3);
+            assert_eq!(v, value_noise(x, 3));
+            assert!(v >= 0.0 && v <= 1.0);
+        }
+    }
+
+    #[test]
+    fn perlin_is_null_on_the_lattice() {
+        let p = Perlin::new(7);
+        for &(x, y, z) in &[(0.0, 0.0, 0.0), (1.0, 2.0, 3.0), (-4.0, 5.0, 250.0)] {
+            assert_eq!(p.noise(x, y, z), 0.0);
+        }
+    }
+
+    #[test]
+    fn perlin_depends_only_on_the_seed() {
+        let (a, b, c) = (Perlin::new(1), Perlin::new(1), Perlin::new(2));
+        let mut differ = false;
+        for i in 0..50 {
+            let (x, y, z) = (i as f64 * 0.31, i as f64 * 0.17 + 0.5, i as f64 * 0.07);
+            let v = a.noise(x, y, z);
+            assert_eq!(v, b.noise(x, y, z));
+            assert!(v >= -1.0 && v <= 1.0);
+            assert!(a.fbm(x, y, z, 4).abs() <= 1.0);
+            differ = differ || v != c.noise(x, y, z);
+        }
+        assert!(differ);
+    }
+}
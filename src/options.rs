@@ -0,0 +1,273 @@
+use particle::RenderMode;
+use blend::BlendMode;
+
+use std::path::PathBuf;
+
+// Command line: <width> <height> <particles by frame> [options]
+// the particles are created at each simulation step
+//   --scene <file>   load (and watch) a scene file
+//   --render-mode <circle|point|streak|sprite>
+//   --sprite <file>  image for the sprite mode, can be given several times:
+//                    --render-mode sprite uses the first one, the scene
+//                    templates choose theirs by index
+//   --sprite-scale <scale>
+//   --blend <alpha|additive|multiply|screen>
+//   --trail <length>  draw the last positions of the particles (default 0)
+//   --software       render with the software rasterizer instead of SDL
+//   --export <dir>   write the frames as numbered PNG files in dir
+//   --export-every <n>  only export one frame every n
+//   --export-size <width>x<height>  resolution of the exported frames and video
+//   --video <-|command>  stream raw RGBA frames to stdout or to the stdin of
+//                    a command (see video::VideoSink)
+//   --video-fps <rate>  frame rate given to the video command (default 60)
+//   --steps-per-frame <n>  simulation steps between two exported frames
+//                    (default 1), the window follows the real time otherwise.
+//                    While recording the window does not follow the real time
+//                    nor --tick-rate, it runs n steps by frame
+//   --fps <rate|uncapped>  frame rate limit of the window (default 60)
+//   --vsync          wait for the screen refresh too
+//   --tick-rate <rate>  simulation steps by second in the window (default 60)
+//   --font <file>    TrueType font of the overlay (system fonts are tried
+//                    when it is not given or cannot be loaded)
+//   --font-size <points>
+//   --resize-world   resize the world bounds with the window
+//   --headless       no window, only run the simulation and export the frames,
+//                    needs --frames
+//   --frames <n>     stop after n simulation frames
+pub struct Options {
+    pub width: u32,
+    pub height: u32,
+    pub parts_by_frame: u32,
+    pub scene: Option<PathBuf>,
+    pub render_mode: RenderMode,
+    pub sprites: Vec<PathBuf>,
+    pub blend_mode: BlendMode,
+    pub trail_length: u16,
+    pub software: bool,
+    pub export: Option<PathBuf>,
+    pub export_every: u32,
+    pub export_size: Option<(u32, u32)>,
+    pub video: Option<String>,
+    pub video_fps: f64,
+    pub steps_per_frame: u32,
+    pub target_fps: Option<f64>,
+    pub vsync: bool,
+    pub tick_rate: f64,
+    pub font: Option<PathBuf>,
+    pub font_size: u16,
+    pub resize_world: bool,
+    pub headless: bool,
+    pub frames: Option<u32>
+}
+
+fn parse_value<T: ::std::str::FromStr>(name: &str, v: Option<&String>) -> Result<T, String> {
+    v.ok_or(format!("{} needs a value", name))?
+        .parse()
+        .map_err(|_| format!("{}: invalid value", name))
+}
+
+impl Options {
+    pub fn from_args(args: &[String]) -> Result<Options, String> {
+        if args.len() < 4 {
+            return Err(format!("usage: {} <width> <height> <particles by frame> [options]",
+                               args[0]));
+        }
+        let mut opt = Options {
+            width: args[1].parse().map_err(|e| format!("width: {}", e))?,
+            height: args[2].parse().map_err(|e| format!("height: {}", e))?,
+            parts_by_frame: args[3].parse().map_err(|e| format!("particles by frame: {}", e))?,
+            scene: None,
+            render_mode: RenderMode::Circle,
+            sprites: vec![],
+            blend_mode: BlendMode::Alpha,
+            trail_length: 0,
+            software: false,
+            export: None,
+            export_every: 1,
+            export_size: None,
+            video: None,
+            video_fps: 60.0,
+            steps_per_frame: 1,
+            target_fps: Some(60.0),
+            vsync: false,
+            tick_rate: 60.0,
+            font: None,
+            font_size: 12,
+            resize_world: false,
+            headless: false,
+            frames: None
+        };
+        let mut render_mode = "circle".to_string();
+        let mut sprite_scale = 1.0;
+        let mut it = args[4..].iter();
+        while let Some(a) = it.next() {
+            match a.as_str() {
+                "--scene" => {
+                    let v = it.next().ok_or("--scene needs a file")?;
+                    opt.scene = Some(PathBuf::from(v));
+                }
+                "--render-mode" => render_mode = parse_value(a, it.next())?,
+                "--sprite" => opt.sprites.push(PathBuf::from(parse_value::<String>(a, it.next())?)),
+                "--sprite-scale" => sprite_scale = parse_value(a, it.next())?,
+                "--trail" => opt.trail_length = parse_value(a, it.next())?,
+                "--software" => opt.software = true,
+                "--export" => opt.export = Some(PathBuf::from(parse_value::<String>(a, it.next())?)),
+                "--export-every" => opt.export_every = parse_value(a, it.next())?,
+                "--export-size" => {
+                    let v = parse_value::<String>(a, it.next())?;
+                    let mut dims = v.split('x').map(|d| d.parse::<u32>());
+                    opt.export_size = match (dims.next(), dims.next(), dims.next()) {
+                        (Some(Ok(w)), Some(Ok(h)), None) if w > 0 && h > 0 => Some((w, h)),
+                        _ => return Err(format!("{}: expected <width>x<height>", a))
+                    };
+                }
+                "--video" => opt.video = Some(parse_value(a, it.next())?),
+                "--video-fps" => opt.video_fps = parse_value(a, it.next())?,
+                "--steps-per-frame" => opt.steps_per_frame = parse_value(a, it.next())?,
+                "--fps" => {
+                    let v = parse_value::<String>(a, it.next())?;
+                    opt.target_fps = if v == "uncapped" {
+                        None
+                    } else {
+                        Some(v.parse().map_err(|_| format!("{}: invalid value", a))?)
+                    };
+                }
+                "--vsync" => opt.vsync = true,
+                "--tick-rate" => opt.tick_rate = parse_value(a, it.next())?,
+                "--font" => opt.font = Some(PathBuf::from(parse_value::<String>(a, it.next())?)),
+                "--font-size" => opt.font_size = parse_value(a, it.next())?,
+                "--resize-world" => opt.resize_world = true,
+                "--headless" => opt.headless = true,
+                "--frames" => opt.frames = Some(parse_value(a, it.next())?),
+                "--blend" => {
+                    let m = parse_value::<String>(a, it.next())?;
+                    opt.blend_mode = BlendMode::from_name(&m).ok_or(format!("unknown blend mode {}", m))?;
+                }
+                _ => return Err(format!("unknown option {}", a))
+            }
+        }
+        opt.render_mode = match render_mode.as_str() {
+            "circle" => RenderMode::Circle,
+            "point" => RenderMode::Point,
+            "streak" => RenderMode::Streak,
+            "sprite" if !opt.sprites.is_empty() => RenderMode::Sprite { index: 0, scale: sprite_scale },
+            "sprite" => return Err("the sprite render mode needs --sprite".to_string()),
+            m => return Err(format!("unknown render mode {}", m))
+        };
+        if opt.export_every == 0 {
+            return Err("--export-every must be at least 1".to_string());
+        }
+        if opt.steps_per_frame == 0 {
+            return Err("--steps-per-frame must be at least 1".to_string());
+        }
+        if opt.target_fps.map_or(false, |f| f <= 0.0) {
+            return Err("--fps must be positive".to_string());
+        }
+        if opt.tick_rate <= 0.0 {
+            return Err("--tick-rate must be positive".to_string());
+        }
+        if opt.video_fps <= 0.0 {
+            return Err("--video-fps must be positive".to_string());
+        }
+        if opt.headless && opt.export.is_none() && opt.video.is_none() {
+            return Err("--headless needs --export or --video".to_string());
+        }
+        if opt.headless && opt.frames.is_none() {
+            return Err("--headless needs --frames, it would never stop".to_string());
+        }
+        Ok(opt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        ::std::iter::once("particle_generator").chain(line.split_whitespace())
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn error(line: &str) -> String {
+        Options::from_args(&args(line)).err().expect("the options should be refused")
+    }
+
+    #[test]
+    fn defaults() {
+        let opt = Options::from_args(&args("800 600 5")).unwrap();
+        assert_eq!((opt.width, opt.height, opt.parts_by_frame), (800, 600, 5));
+        assert_eq!(opt.render_mode, RenderMode::Circle);
+        assert!(opt.scene.is_none() && opt.sprites.is_empty());
+    }
+
+    #[test]
+    fn render_modes() {
+        let opt = Options::from_args(&args("800 600 5 --render-mode streak")).unwrap();
+        assert_eq!(opt.render_mode, RenderMode::Streak);
+        let opt = Options::from_args(&args("800 600 5 --render-mode sprite --sprite a.png --sprite b.png \
+                                            --sprite-scale 2")).unwrap();
+        assert_eq!(opt.render_mode, RenderMode::Sprite { index: 0, scale: 2.0 });
+        assert_eq!(opt.sprites, vec![PathBuf::from("a.png"), PathBuf::from("b.png")]);
+        assert!(error("800 600 5 --render-mode sprite").contains("needs --sprite"));
+        assert!(error("800 600 5 --render-mode cube").contains("unknown render mode cube"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(error("800 600").starts_with("usage:"));
+        assert!(error("800 x 5").starts_with("height:"));
+        assert!(error("800 600 5 --sprite-scale").contains("needs a value"));
+        assert!(error("800 600 5 --verbose").contains("unknown option --verbose"));
+    }
+
+    #[test]
+    fn blend_modes() {
+        assert_eq!(Options::from_args(&args("800 600 5")).unwrap().blend_mode, BlendMode::Alpha);
+        let opt = Options::from_args(&args("800 600 5 --blend screen")).unwrap();
+        assert_eq!(opt.blend_mode, BlendMode::Screen);
+        assert!(error("800 600 5 --blend darken").contains("unknown blend mode darken"));
+    }
+
+    #[test]
+    fn trail_length() {
+        assert_eq!(Options::from_args(&args("800 600 5")).unwrap().trail_length, 0);
+        assert_eq!(Options::from_args(&args("800 600 5 --trail 8")).unwrap().trail_length, 8);
+        assert!(error("800 600 5 --trail -1").contains("invalid value"));
+    }
+
+    #[test]
+    fn export() {
+        let opt = Options::from_args(&args("800 600 5 --export out --export-every 2 \
+                                            --export-size 1920x1080")).unwrap();
+        assert_eq!(opt.export, Some(PathBuf::from("out")));
+        assert_eq!(opt.export_every, 2);
+        assert_eq!(opt.export_size, Some((1920, 1080)));
+        assert!(!opt.headless);
+        assert!(error("800 600 5 --export-size 10x").contains("<width>x<height>"));
+        assert!(error("800 600 5 --export-size 0x10").contains("<width>x<height>"));
+        assert!(error("800 600 5 --export-every 0").contains("at least 1"));
+        assert!(error("800 600 5 --headless").contains("--export or --video"));
+        assert!(error("800 600 5 --headless --export out").contains("--frames"));
+        assert!(Options::from_args(&args("800 600 5 --headless --export out --frames 10")).is_ok());
+    }
+
+    #[test]
+    fn font() {
+        let opt = Options::from_args(&args("800 600 5 --font f.ttf --font-size 14")).unwrap();
+        assert_eq!(opt.font, Some(PathBuf::from("f.ttf")));
+        assert_eq!(opt.font_size, 14);
+        assert!(Options::from_args(&args("800 600 5")).unwrap().font.is_none());
+    }
+
+    #[test]
+    fn frame_rate() {
+        let opt = Options::from_args(&args("800 600 5")).unwrap();
+        assert_eq!((opt.target_fps, opt.tick_rate), (Some(60.0), 60.0));
+        let opt = Options::from_args(&args("800 600 5 --fps uncapped --tick-rate 120")).unwrap();
+        assert_eq!((opt.target_fps, opt.tick_rate), (None, 120.0));
+        assert!(error("800 600 5 --fps 0").contains("positive"));
+        assert!(error("800 600 5 --tick-rate 0").contains("positive"));
+        assert!(error("800 600 5 --tick-rate").contains("needs a value"));
+    }
+}
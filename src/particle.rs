@@ -1,38 +1,74 @@
 
 use vec3d::Vec3;
 use point3d::Point3;
-use drawable::Drawable;
-
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-use sdl2::surface::Surface;
-use sdl2::gfx::primitives::DrawRenderer;
+use drawable::{Drawable, DrawList, DrawCommand};
+use blend::BlendMode;
 
 static PARTICLE_DENSITY : f64 = 1.0;
+// length of a streak, in tick of movement
+static STREAK_LENGTH : f64 = 0.3;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RenderMode {
+    Circle,
+    Point,
+    // line from the position back along the direction
+    Streak,
+    // index of the image in the sprites loaded by main, rotated along the direction
+    Sprite { index: usize, scale: f64 }
+}
+
+impl Default for RenderMode {
+    fn default() -> RenderMode {
+        RenderMode::Circle
+    }
+}
+
+// Unique id of a particle, stays valid when the world compacts its particle list
+#[derive(Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct ParticleHandle(pub u64);
 
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
 pub struct Particle {
+    id: ParticleHandle, // given by the world
     position: Point3<i16>,
     direction: Vec3<i16>,
     alive: bool,
     lifetime: u64, //tick
     max_lifetime: u64, //tick
     radius: i16,
-    mass: f64
+    mass: f64,
+    color: Option<(u8, u8, u8)>, // None: from red to yellow with the age
+    sub_emitter: Option<usize>, // index in `World::sub_emitters`
+    trail_length: u16, // number of previous positions kept by the world
+    render_mode: RenderMode,
+    blend_mode: BlendMode
 }
 
 impl Particle {
     pub fn new(p: Point3<i16>, d: Vec3<i16>) -> Particle {
         Particle {
+            id: ParticleHandle(0),
             position: p,
             direction: d,
             alive: true,
             lifetime: 250,
             max_lifetime: 250,
             radius: 5,
-            mass: 5.0 * PARTICLE_DENSITY
+            mass: 5.0 * PARTICLE_DENSITY,
+            color: None,
+            sub_emitter: None,
+            trail_length: 0,
+            render_mode: RenderMode::Circle,
+            blend_mode: BlendMode::Alpha
         }
     }
+    pub fn handle(&self) -> ParticleHandle {
+        self.id
+    }
+    pub fn set_handle(&mut self, h: ParticleHandle) {
+        self.id = h;
+    }
     pub fn set_position(&mut self, pos: Point3<i16>) {
         self.position = pos;
     }
@@ -54,16 +90,40 @@ impl Particle {
     pub fn get_mass(&self) -> f64 {
         self.mass
     }
+    pub fn get_color(&self) -> Option<(u8, u8, u8)> {
+        self.color
+    }
+    pub fn set_color(&mut self, c: Option<(u8, u8, u8)>) {
+        self.color = c;
+    }
+    pub fn get_sub_emitter(&self) -> Option<usize> {
+        self.sub_emitter
+    }
+    pub fn get_trail_length(&self) -> u16 {
+        self.trail_length
+    }
+    pub fn get_render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+    pub fn get_blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
 
     pub fn copy(&self) -> Particle {
         Particle {
+            id: self.id,
             position: self.position,
             direction: self.direction,
             alive: self.alive,
             lifetime: self.lifetime,
             max_lifetime: self.max_lifetime,
             radius: self.radius,
-            mass: self.mass
+            mass: self.mass,
+            color: self.color,
+            sub_emitter: self.sub_emitter,
+            trail_length: self.trail_length,
+            render_mode: self.render_mode,
+            blend_mode: self.blend_mode
         }
     }
     pub fn update(&mut self) {
@@ -94,6 +154,12 @@ impl Particle {
             ) * 255.0
         ) as u8
     }
+    pub fn compute_color(&self) -> (u8, u8, u8, u8) {
+        match self.color {
+            Some((r, g, b)) => (r, g, b, self.compute_alpha()),
+            None => (255, self.compute_green(), 0, self.compute_alpha())
+        }
+    }
     fn change_radius(&mut self, rad: i16) {
         self.radius = rad;
         self.mass = (rad as f64) * PARTICLE_DENSITY;
@@ -101,17 +167,36 @@ impl Particle {
 }
 
 impl Drawable for Particle {
-    fn draw_surface(&self, c: &mut Canvas<Surface>) {
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        self.radius,
-                        (255, self.compute_green(), 0, self.compute_alpha())).unwrap();
-    }
-    fn draw_window(&self, c: &mut Canvas<Window>) {
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        self.radius,
-                        (255, self.compute_green(), 0, self.compute_alpha())).unwrap();
+    fn draw(&self, list: &mut DrawList) {
+        let x = self.position.x as i16;
+        let y = self.position.y as i16;
+        let color = self.compute_color();
+        let tail_x = (self.position.x - self.direction.x * STREAK_LENGTH) as i16;
+        let tail_y = (self.position.y - self.direction.y * STREAK_LENGTH) as i16;
+        match self.render_mode {
+            RenderMode::Point => list.push(DrawCommand::Pixel {
+                x: x, y: y, color: color, blend: self.blend_mode
+            }),
+            RenderMode::Streak if self.radius > 1 => list.push(DrawCommand::ThickLine {
+                x1: tail_x, y1: tail_y, x2: x, y2: y, width: self.radius as u8, color: color,
+                blend: self.blend_mode
+            }),
+            RenderMode::Streak => list.push(DrawCommand::AaLine {
+                x1: tail_x, y1: tail_y, x2: x, y2: y, color: color, blend: self.blend_mode
+            }),
+            RenderMode::Circle => list.push(DrawCommand::FilledCircle {
+                x: x, y: y, radius: self.radius, color: color, blend: self.blend_mode
+            }),
+            RenderMode::Sprite { index, scale } => list.push(DrawCommand::Sprite {
+                index: index,
+                x: self.position.x,
+                y: self.position.y,
+                scale: scale,
+                angle: self.direction.y.atan2(self.direction.x).to_degrees(),
+                color: color,
+                blend: self.blend_mode
+            })
+        }
     }
 }
 
@@ -135,6 +220,28 @@ impl<'a> ParticleBuilder {
         self.template.lifetime = lifetime;
         self
     }
+    pub fn with_color(&'a mut self, r: u8, g: u8, b: u8) -> &'a mut ParticleBuilder {
+        self.template.color = Some((r, g, b));
+        self
+    }
+    // index of the sub emitter in the world
+    pub fn with_sub_emitter(&'a mut self, sub_emitter: usize) -> &'a mut ParticleBuilder {
+        self.template.sub_emitter = Some(sub_emitter);
+        self
+    }
+    // keep the last `length` positions to draw a trail, 0 to disable
+    pub fn with_trail(&'a mut self, length: u16) -> &'a mut ParticleBuilder {
+        self.template.trail_length = length;
+        self
+    }
+    pub fn with_render_mode(&'a mut self, mode: RenderMode) -> &'a mut ParticleBuilder {
+        self.template.render_mode = mode;
+        self
+    }
+    pub fn with_blend_mode(&'a mut self, mode: BlendMode) -> &'a mut ParticleBuilder {
+        self.template.blend_mode = mode;
+        self
+    }
     pub fn create(&self) -> Particle {
         self.template.clone()
     }
@@ -1,32 +1,69 @@
 use particle::Particle;
 use vec3::Vec3;
 use vec3::unit_vector;
+use noise::{value_noise, Perlin};
 use point3::Point3;
-use drawable::Drawable;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-use sdl2::surface::Surface;
-use sdl2::gfx::primitives::DrawRenderer;
-//use sdl2::render::RenderTarget;
+use rectangle::Rectangle;
+use drawable::{Drawable, DrawList};
 use std::f64::consts::PI;
 use std::vec::Vec;
-use std;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Parameter {
+    // key given to `set_parameter`
+    pub name: &'static str,
+    // label shown in the inspector
+    pub description: &'static str,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    pub step: f64
+}
+
+impl Parameter {
+    pub fn new(name: &'static str, value: f64, min: f64, max: f64, step: f64) -> Parameter {
+        Parameter::with_description(name, name, value, min, max, step)
+    }
+    pub fn with_description(name: &'static str, description: &'static str, value: f64, min: f64, max: f64,
+                            step: f64) -> Parameter {
+        Parameter {
+            name: name,
+            description: description,
+            value: value,
+            min: min,
+            max: max,
+            step: step
+        }
+    }
+}
 
 pub trait PhysicProperty : Send + Sync {
     //type DrawableEntity: Drawable;
-    fn update_particle(&self, p: &Particle) -> Particle;
+    // time is the number of ticks since the start of the simulation
+    fn update_particle(&self, p: &Particle, time: f64) -> Particle;
     //fn as_drawable(&self) -> &Self::DrawableEntity;
     fn as_drawable(&self) -> Option<&Drawable>;
+    fn name(&self) -> &str;
+    // tweakable values, shown in the inspector
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![]
+    }
+    fn set_parameter(&mut self, _name: &str, _value: f64) {}
+    // number of particles killed by the property, if it kills any
+    fn absorbed(&self) -> Option<usize> {
+        None
+    }
+    // area where the property acts, used for the enter events
+    fn contains(&self, _p: &Point3) -> bool {
+        false
+    }
 }
-// struct Void {}
-// impl Drawable for Void {
-//     fn draw<T: RenderTarget>(&self, canvas: Canvas<T>) {}
-// }
 
 pub struct Gravity {}
 impl PhysicProperty for Gravity {
     //type DrawableEntity = Void;
-    fn update_particle(&self, p: &Particle) -> Particle {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
         let mut tmp = p.clone();
         tmp.apply_force(Vec3::new(0.0, 1.0 * (p.get_mass() as f64), 0.0));
         tmp
@@ -34,14 +71,81 @@ impl PhysicProperty for Gravity {
     fn as_drawable(&self) -> Option<&Drawable> {
         None
     }
+    fn name(&self) -> &str {
+        "Gravity"
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Gust {
+    Steady,
+    Sine,
+    Noise
 }
 
-pub struct Wind {}
+// Wind blowing toward `direction`, its strength vary with time (gusts) and
+// across the world (perpendicularly to the direction)
+pub struct Wind {
+    pub direction: Vec3,
+    pub strength: f64,
+    pub gust: Gust,
+    pub gust_amplitude: f64, // fraction of the strength
+    pub gust_period: f64, // tick
+    pub spatial_amplitude: f64, // fraction of the strength
+    pub spatial_wavelength: f64
+}
+impl Wind {
+    pub fn new() -> Wind {
+        Wind::from_force(Vec3::new(-0.25, 0.0, 0.0))
+    }
+    pub fn from_force(f: Vec3) -> Wind {
+        let strength = f.length();
+        Wind {
+            direction: if strength > 0.0 { f / strength } else { Vec3::new(1.0, 0.0, 0.0) },
+            strength: strength,
+            gust: Gust::Steady,
+            gust_amplitude: 0.0,
+            gust_period: 100.0,
+            spatial_amplitude: 0.0,
+            spatial_wavelength: 200.0
+        }
+    }
+    pub fn with_gust(mut self, g: Gust, amplitude: f64, period: f64) -> Wind {
+        self.gust = g;
+        self.gust_amplitude = amplitude;
+        self.gust_period = period.max(1.0);
+        self
+    }
+    pub fn with_spatial_variation(mut self, amplitude: f64, wavelength: f64) -> Wind {
+        self.spatial_amplitude = amplitude;
+        self.spatial_wavelength = wavelength.max(1.0);
+        self
+    }
+    fn gust_factor(&self, time: f64) -> f64 {
+        let t = time / self.gust_period;
+        match self.gust {
+            Gust::Steady => 1.0,
+            Gust::Sine => 1.0 + self.gust_amplitude * (2.0 * PI * t).sin(),
+            Gust::Noise => 1.0 + self.gust_amplitude * (2.0 * value_noise(t, 0) - 1.0)
+        }
+    }
+    fn spatial_factor(&self, pos: &Point3, time: f64) -> f64 {
+        if self.spatial_amplitude == 0.0 {
+            return 1.0;
+        }
+        // position along the perpendicular of the direction
+        let s = (pos.y * self.direction.x - pos.x * self.direction.y) / self.spatial_wavelength;
+        1.0 + self.spatial_amplitude * (2.0 * value_noise(s + time / self.gust_period, 1) - 1.0)
+    }
+    pub fn force_at(&self, pos: &Point3, time: f64) -> Vec3 {
+        self.direction * (self.strength * self.gust_factor(time) * self.spatial_factor(pos, time))
+    }
+}
 impl PhysicProperty for Wind {
     // type DrawableEntity = Void;
-    fn update_particle(&self, p: &Particle) -> Particle {
+    fn update_particle(&self, p: &Particle, time: f64) -> Particle {
         let mut tmp = p.clone();
-        tmp.apply_force(Vec3::new(-0.25, 0.0, 0.0));
+        tmp.apply_force(self.force_at(&p.get_position(), time));
         tmp
     }
     // fn as_drawable(&self) -> &Self::DrawableEntity {
@@ -50,6 +154,125 @@ impl PhysicProperty for Wind {
     fn as_drawable(&self) -> Option<&Drawable> {
         None
     }
+    fn name(&self) -> &str {
+        "Wind"
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        let gust = match self.gust {
+            Gust::Steady => 0.0,
+            Gust::Sine => 1.0,
+            Gust::Noise => 2.0
+        };
+        vec![Parameter::with_description("direction", "direction (deg)",
+                                         self.direction.y.atan2(self.direction.x).to_degrees(),
+                                         -180.0, 180.0, 5.0),
+             Parameter::new("strength", self.strength, 0.0, 5.0, 0.05),
+             Parameter::with_description("gust", "gust (0 steady, 1 sine, 2 noise)", gust, 0.0, 2.0, 1.0),
+             Parameter::with_description("gust_amplitude", "gust amplitude",
+                                         self.gust_amplitude, 0.0, 2.0, 0.05),
+             Parameter::with_description("gust_period", "gust period", self.gust_period, 1.0, 1000.0, 10.0),
+             Parameter::with_description("spatial_amplitude", "spatial amplitude",
+                                         self.spatial_amplitude, 0.0, 2.0, 0.05),
+             Parameter::with_description("spatial_wavelength", "spatial wavelength",
+                                         self.spatial_wavelength, 1.0, 2000.0, 10.0)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "direction" => {
+                let a = value.to_radians();
+                self.direction = Vec3::new(a.cos(), a.sin(), 0.0);
+            }
+            "strength" => self.strength = value,
+            "gust" => {
+                self.gust = match value.round() as i64 {
+                    1 => Gust::Sine,
+                    2 => Gust::Noise,
+                    _ => Gust::Steady
+                }
+            }
+            "gust_amplitude" => self.gust_amplitude = value,
+            "gust_period" => self.gust_period = value.max(1.0),
+            "spatial_amplitude" => self.spatial_amplitude = value,
+            "spatial_wavelength" => self.spatial_wavelength = value.max(1.0),
+            _ => {}
+        }
+    }
+}
+
+// Swirling force following a noise field, in the xy plane (the third noise
+// dimension is the time). With `curl` the force is the 2D curl of a noise
+// potential, (dpsi/dy, -dpsi/dx), which gives a divergence free
+// (incompressible) flow, otherwise it is directly a noise vector.
+pub struct Turbulence {
+    pub frequency: f64,
+    pub amplitude: f64,
+    pub octaves: u32,
+    pub evolution_speed: f64, // noise unit by tick
+    pub curl: bool,
+    noise: [Perlin; 2]
+}
+impl Turbulence {
+    pub fn new(frequency: f64, amplitude: f64, octaves: u32, evolution_speed: f64) -> Turbulence {
+        Turbulence {
+            frequency: frequency,
+            amplitude: amplitude,
+            octaves: octaves,
+            evolution_speed: evolution_speed,
+            curl: true,
+            noise: [Perlin::new(0), Perlin::new(1)]
+        }
+    }
+    fn potential(&self, x: f64, y: f64, t: f64) -> f64 {
+        self.noise[0].fbm(x, y, t, self.octaves)
+    }
+    // Never has a z component, the particles stay in their plane
+    pub fn force_at(&self, pos: &Point3, time: f64) -> Vec3 {
+        let x = pos.x * self.frequency;
+        let y = pos.y * self.frequency;
+        let t = time * self.evolution_speed;
+        if !self.curl {
+            return Vec3::new(self.noise[0].fbm(x, y, t, self.octaves),
+                             self.noise[1].fbm(x, y, t, self.octaves),
+                             0.0) * self.amplitude;
+        }
+        // central differences
+        let e = 1e-2;
+        let dx = (self.potential(x + e, y, t) - self.potential(x - e, y, t)) / (2.0 * e);
+        let dy = (self.potential(x, y + e, t) - self.potential(x, y - e, t)) / (2.0 * e);
+        Vec3::new(dy, -dx, 0.0) * self.amplitude
+    }
+}
+impl PhysicProperty for Turbulence {
+    fn update_particle(&self, p: &Particle, time: f64) -> Particle {
+        let mut tmp = p.clone();
+        tmp.apply_force(self.force_at(&p.get_position(), time));
+        tmp
+    }
+    fn as_drawable(&self) -> Option<&Drawable> {
+        None
+    }
+    fn name(&self) -> &str {
+        "Turbulence"
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("frequency", self.frequency, 0.0001, 0.1, 0.0005),
+             Parameter::new("amplitude", self.amplitude, 0.0, 20.0, 0.1),
+             Parameter::new("octaves", self.octaves as f64, 1.0, 8.0, 1.0),
+             Parameter::with_description("evolution_speed", "evolution speed",
+                                         self.evolution_speed, 0.0, 0.1, 0.001),
+             Parameter::with_description("curl", "curl (0 off, 1 on)",
+                                         if self.curl { 1.0 } else { 0.0 }, 0.0, 1.0, 1.0)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "frequency" => self.frequency = value,
+            "amplitude" => self.amplitude = value,
+            "octaves" => self.octaves = value.round().max(1.0) as u32,
+            "evolution_speed" => self.evolution_speed = value,
+            "curl" => self.curl = value >= 0.5,
+            _ => {}
+        }
+    }
 }
 
 struct SqrtCache {
@@ -91,7 +314,7 @@ impl AirResistance {
 }
 impl PhysicProperty for AirResistance {
     //type DrawableEntity = Void;
-    fn update_particle(&self, p: &Particle) -> Particle {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
         let density = 1.0; // air density
         let drag = 0.020; // drag coeficient (magic number here)
         let area = PI * (p.get_radius() as f64);// area affected by the air resistance, compute using radius of sphere
@@ -113,6 +336,9 @@ impl PhysicProperty for AirResistance {
     fn as_drawable(&self) -> Option<&Drawable> {
         None
     }
+    fn name(&self) -> &str {
+        "Air resistance"
+    }
 }
 
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
@@ -131,55 +357,18 @@ impl GravityWell {
     }
 }
 impl Drawable for GravityWell {
-    //fn draw<T: RenderTarget>(&self, c: &mut Canvas<T>) {
-    fn draw_window(&self, c: &mut Canvas<Window>) {
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        (self.area_of_effect * 3.0) as i16,
-                        (0, 0, 255, 100)
-        ).unwrap();
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        (self.area_of_effect * 2.0) as i16,
-                        (0, 0, 255, 150)
-        ).unwrap();
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        self.area_of_effect as i16,
-                        (0, 0, 255, 200)
-        ).unwrap();
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        5,
-                        (0, 0, 255, 255)
-        ).unwrap();
-    }
-    fn draw_surface(&self, c: &mut Canvas<Surface>) {
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        (self.area_of_effect * 3.0) as i16,
-                        (0, 0, 255, 100)
-        ).unwrap();
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        (self.area_of_effect * 2.0) as i16,
-                        (0, 0, 255, 150)
-        ).unwrap();
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        self.area_of_effect as i16,
-                        (0, 0, 255, 200)
-        ).unwrap();
-        c.filled_circle(self.position.x as i16,
-                        self.position.y as i16,
-                        5,
-                        (0, 0, 255, 255)
-        ).unwrap();
+    fn draw(&self, list: &mut DrawList) {
+        let x = self.position.x as i16;
+        let y = self.position.y as i16;
+        list.filled_circle(x, y, (self.area_of_effect * 3.0) as i16, (0, 0, 255, 100));
+        list.filled_circle(x, y, (self.area_of_effect * 2.0) as i16, (0, 0, 255, 150));
+        list.filled_circle(x, y, self.area_of_effect as i16, (0, 0, 255, 200));
+        list.filled_circle(x, y, 5, (0, 0, 255, 255));
     }
 }
 impl PhysicProperty for GravityWell {
     //type DrawableEntity = GravityWell;
-    fn update_particle(&self, p: &Particle) -> Particle {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
         let dist = ((self.position.x - p.get_position().x) *
                     (self.position.x - p.get_position().x)) +
                    ((self.position.y - p.get_position().y) *
@@ -208,6 +397,26 @@ impl PhysicProperty for GravityWell {
     fn as_drawable(&self) -> Option<&Drawable> {
         Some(self)
     }
+    fn name(&self) -> &str {
+        "Gravity well"
+    }
+    fn contains(&self, p: &Point3) -> bool {
+        let aoe3 = self.area_of_effect * 3.0;
+        (self.position.x - p.x) * (self.position.x - p.x) +
+            (self.position.y - p.y) * (self.position.y - p.y) < aoe3 * aoe3
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("strength", self.strength, 0.0, 50.0, 0.5),
+             Parameter::with_description("area_of_effect", "area of effect",
+                                         self.area_of_effect, 1.0, 200.0, 1.0)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "strength" => self.strength = value,
+            "area_of_effect" => self.area_of_effect = value,
+            _ => {}
+        }
+    }
 }
 
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
@@ -228,31 +437,14 @@ impl BigGravityWell {
     }
 }
 impl Drawable for BigGravityWell {
-    //fn draw<T: RenderTarget>(&self, c: &mut Canvas<T>) {
-    fn draw_window(&self, c: &mut Canvas<Window>) {
-        let mut alpha = 55;
-        let mut aoe = self.area_of_effect;
-        for i in 0..self.layers {
-            let green = std::cmp::min(255, i*12) as u8;
-            c.filled_circle(self.position.x as i16,
-                            self.position.y as i16,
-                            (aoe) as i16,
-                            (0, green, 255, alpha)
-            ).unwrap();
-            alpha += 20;
-            aoe = (aoe * 1.5) + self.area_of_effect;
-        }
-    }
-    fn draw_surface(&self, c: &mut Canvas<Surface>) {
+    fn draw(&self, list: &mut DrawList) {
         let mut alpha = 255;
         let mut aoe = self.area_of_effect;
         for _ in 0..self.layers {
-            // let green = std::cmp::min(255, i*12) as u8;
-            c.filled_circle(self.position.x as i16,
-                            self.position.y as i16,
-                            (aoe) as i16,
-                            (0, 0, 255, alpha as u8)
-            ).unwrap();
+            list.filled_circle(self.position.x as i16,
+                               self.position.y as i16,
+                               aoe as i16,
+                               (0, 0, 255, alpha as u8));
             alpha -= 255/(self.layers+1);
             aoe = (aoe * 1.5) + self.area_of_effect;
         }
@@ -260,7 +452,7 @@ impl Drawable for BigGravityWell {
 }
 impl PhysicProperty for BigGravityWell {
     //type DrawableEntity = BigGravityWell;
-    fn update_particle(&self, p: &Particle) -> Particle {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
         let dist = ((self.position.x - p.get_position().x) *
                     (self.position.x - p.get_position().x)) +
                    ((self.position.y - p.get_position().y) *
@@ -288,4 +480,350 @@ impl PhysicProperty for BigGravityWell {
     fn as_drawable(&self) -> Option<&Drawable> {
         Some(self)
     }
+    fn name(&self) -> &str {
+        "Big gravity well"
+    }
+    fn contains(&self, p: &Point3) -> bool {
+        let mut aoe = self.area_of_effect;
+        for _ in 1..self.layers {
+            aoe = (aoe * 1.5) + self.area_of_effect;
+        }
+        (self.position.x - p.x) * (self.position.x - p.x) +
+            (self.position.y - p.y) * (self.position.y - p.y) < aoe * aoe
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("strength", self.strength, 0.0, 50.0, 0.1),
+             Parameter::with_description("area_of_effect", "area of effect",
+                                         self.area_of_effect, 1.0, 200.0, 1.0),
+             Parameter::new("layers", self.layers as f64, 1.0, 30.0, 1.0)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "strength" => self.strength = value,
+            "area_of_effect" => self.area_of_effect = value,
+            "layers" => self.layers = value.round().max(1.0) as u64,
+            _ => {}
+        }
+    }
+}
+
+// Rotation around an axis going through `position`, the tangential force
+// decrease from the axis to `radius` following (1 - d/radius)^falloff, the
+// particles are also pulled toward the axis by `inward_pull`
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Vortex {
+    pub position: Point3,
+    pub axis: Vec3,
+    pub radius: f64,
+    pub strength: f64, // negative to turn clockwise
+    pub falloff: f64,
+    pub inward_pull: f64
+}
+impl Vortex {
+    pub fn new(p: Point3, radius: f64, strength: f64) -> Vortex {
+        Vortex {
+            position: p,
+            axis: Vec3::new(0.0, 0.0, 1.0),
+            radius: radius,
+            strength: strength,
+            falloff: 1.0,
+            inward_pull: 0.0
+        }
+    }
+    pub fn with_axis(mut self, axis: Vec3) -> Vortex {
+        self.axis = unit_vector(axis);
+        self
+    }
+    pub fn with_falloff(mut self, falloff: f64) -> Vortex {
+        self.falloff = falloff;
+        self
+    }
+    pub fn with_inward_pull(mut self, pull: f64) -> Vortex {
+        self.inward_pull = pull;
+        self
+    }
+    pub fn force_at(&self, pos: &Point3) -> Vec3 {
+        let a = self.axis;
+        let offset = Vec3::new(pos.x - self.position.x,
+                               pos.y - self.position.y,
+                               pos.z - self.position.z);
+        // offset projected on the plane orthogonal to the axis
+        let along = offset.x * a.x + offset.y * a.y + offset.z * a.z;
+        let radial = offset - a * along;
+        let dist = radial.length();
+        if dist >= self.radius || dist < 1e-6 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let factor = (1.0 - dist / self.radius).powf(self.falloff);
+        let tangent = Vec3::new(a.y * radial.z - a.z * radial.y,
+                                a.z * radial.x - a.x * radial.z,
+                                a.x * radial.y - a.y * radial.x) / dist;
+        tangent * (self.strength * factor) - (radial / dist) * (self.inward_pull * factor)
+    }
+}
+impl Drawable for Vortex {
+    fn draw(&self, list: &mut DrawList) {
+        let x = self.position.x as i16;
+        let y = self.position.y as i16;
+        list.filled_circle(x, y, self.radius as i16, (0, 200, 200, 40));
+        list.circle(x, y, self.radius as i16, (0, 200, 200, 150));
+        // arrows showing the direction of rotation
+        let arrows = 8;
+        for i in 0..arrows {
+            let angle = 2.0 * PI * (i as f64) / (arrows as f64);
+            let r = self.radius * 0.6;
+            let p = Point3::new(self.position.x + r * angle.cos(),
+                                self.position.y + r * angle.sin(),
+                                self.position.z);
+            let f = self.force_at(&p);
+            if f.length() == 0.0 {
+                continue;
+            }
+            let f = unit_vector(f) * (self.radius * 0.25);
+            let end = (p.x + f.x, p.y + f.y);
+            list.line(p.x as i16, p.y as i16, end.0 as i16, end.1 as i16, (0, 255, 255, 200));
+            list.filled_circle(end.0 as i16, end.1 as i16, 2, (0, 255, 255, 200));
+        }
+        list.filled_circle(x, y, 3, (0, 255, 255, 255));
+    }
+}
+impl PhysicProperty for Vortex {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
+        let mut tmp = p.clone();
+        tmp.apply_force(self.force_at(&p.get_position()));
+        tmp
+    }
+    fn as_drawable(&self) -> Option<&Drawable> {
+        Some(self)
+    }
+    fn name(&self) -> &str {
+        "Vortex"
+    }
+    fn contains(&self, p: &Point3) -> bool {
+        (self.position.x - p.x) * (self.position.x - p.x) +
+            (self.position.y - p.y) * (self.position.y - p.y) < self.radius * self.radius
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("radius", self.radius, 1.0, 1000.0, 5.0),
+             Parameter::new("strength", self.strength, -20.0, 20.0, 0.1),
+             Parameter::new("falloff", self.falloff, 0.0, 5.0, 0.1),
+             Parameter::with_description("inward_pull", "inward pull", self.inward_pull, -20.0, 20.0, 0.1),
+             Parameter::with_description("axis_x", "axis x", self.axis.x, -1.0, 1.0, 0.05),
+             Parameter::with_description("axis_y", "axis y", self.axis.y, -1.0, 1.0, 0.05),
+             Parameter::with_description("axis_z", "axis z", self.axis.z, -1.0, 1.0, 0.05)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        let a = self.axis;
+        let axis = match name {
+            "radius" => { self.radius = value; return; }
+            "strength" => { self.strength = value; return; }
+            "falloff" => { self.falloff = value; return; }
+            "inward_pull" => { self.inward_pull = value; return; }
+            "axis_x" => Vec3::new(value, a.y, a.z),
+            "axis_y" => Vec3::new(a.x, value, a.z),
+            "axis_z" => Vec3::new(a.x, a.y, value),
+            _ => return
+        };
+        // the axis stays normalized, a null one is refused
+        if axis.length() > 1e-6 {
+            self.axis = unit_vector(axis);
+        }
+    }
+}
+
+// Point attractor with a physically based falloff: the force is
+// strength * mass / (d^2 + softening^2)^(exponent/2), exponent = 2 gives the
+// inverse-square law. The softening avoid the singularity at the center, a
+// negative strength repulse the particles.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Attractor {
+    pub position: Point3,
+    pub strength: f64,
+    pub exponent: f64,
+    pub softening: f64,
+    pub max_range: Option<f64>
+}
+impl Attractor {
+    pub fn new(p: Point3, strength: f64) -> Attractor {
+        Attractor {
+            position: p,
+            strength: strength,
+            exponent: 2.0,
+            softening: 5.0,
+            max_range: None
+        }
+    }
+    pub fn with_exponent(mut self, exponent: f64) -> Attractor {
+        self.exponent = exponent;
+        self
+    }
+    pub fn with_softening(mut self, softening: f64) -> Attractor {
+        self.softening = softening;
+        self
+    }
+    pub fn with_max_range(mut self, range: f64) -> Attractor {
+        self.max_range = Some(range);
+        self
+    }
+    pub fn force_at(&self, pos: &Point3, mass: f64) -> Vec3 {
+        let to_center = Vec3::new(self.position.x - pos.x,
+                                  self.position.y - pos.y,
+                                  self.position.z - pos.z);
+        let d2 = to_center.squared_length();
+        if let Some(r) = self.max_range {
+            if d2 > r * r {
+                return Vec3::new(0.0, 0.0, 0.0);
+            }
+        }
+        let d = d2.sqrt();
+        if d == 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let soft2 = d2 + self.softening * self.softening;
+        (to_center / d) * (self.strength * mass / soft2.powf(self.exponent / 2.0))
+    }
+}
+impl Drawable for Attractor {
+    fn draw(&self, list: &mut DrawList) {
+        let x = self.position.x as i16;
+        let y = self.position.y as i16;
+        let color = if self.strength >= 0.0 { (80, 80, 255) } else { (255, 80, 80) };
+        if let Some(r) = self.max_range {
+            list.filled_circle(x, y, r as i16, (color.0, color.1, color.2, 30));
+            list.circle(x, y, r as i16, (color.0, color.1, color.2, 120));
+        }
+        list.filled_circle(x, y, self.softening.max(2.0) as i16, (color.0, color.1, color.2, 255));
+    }
+}
+impl PhysicProperty for Attractor {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
+        let mut tmp = p.clone();
+        tmp.apply_force(self.force_at(&p.get_position(), p.get_mass()));
+        tmp
+    }
+    fn as_drawable(&self) -> Option<&Drawable> {
+        Some(self)
+    }
+    fn name(&self) -> &str {
+        "Attractor"
+    }
+    fn contains(&self, p: &Point3) -> bool {
+        match self.max_range {
+            Some(r) => (self.position.x - p.x) * (self.position.x - p.x) +
+                (self.position.y - p.y) * (self.position.y - p.y) < r * r,
+            None => false
+        }
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::new("strength", self.strength, -20000.0, 20000.0, 100.0),
+             Parameter::new("exponent", self.exponent, 0.0, 4.0, 0.1),
+             Parameter::new("softening", self.softening, 0.0, 100.0, 1.0),
+             Parameter::with_description("max_range", "max range (0 = none)",
+                                         self.max_range.unwrap_or(0.0), 0.0, 2000.0, 10.0)]
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        match name {
+            "strength" => self.strength = value,
+            "exponent" => self.exponent = value,
+            "softening" => self.softening = value,
+            "max_range" => self.max_range = if value > 0.0 { Some(value) } else { None },
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Region {
+    Circle { center: Point3, radius: f64 },
+    Box(Rectangle)
+}
+impl Region {
+    pub fn contains(&self, p: &Point3) -> bool {
+        match *self {
+            Region::Circle { center, radius } => {
+                let dx = p.x - center.x;
+                let dy = p.y - center.y;
+                let dz = p.z - center.z;
+                dx * dx + dy * dy + dz * dz <= radius * radius
+            }
+            Region::Box(r) => r.is_in_bound(p)
+        }
+    }
+}
+
+// Kill every particle entering the region and count them
+pub struct KillZone {
+    pub region: Region,
+    absorbed: AtomicUsize
+}
+impl KillZone {
+    pub fn new(r: Region) -> KillZone {
+        KillZone {
+            region: r,
+            absorbed: AtomicUsize::new(0)
+        }
+    }
+    // Black hole like sink, to put at the core of a well
+    pub fn sink(center: Point3, radius: f64) -> KillZone {
+        KillZone::new(Region::Circle { center: center, radius: radius })
+    }
+    pub fn rectangle(r: Rectangle) -> KillZone {
+        KillZone::new(Region::Box(r))
+    }
+}
+impl Drawable for KillZone {
+    fn draw(&self, list: &mut DrawList) {
+        match self.region {
+            Region::Circle { center, radius } => {
+                list.filled_circle(center.x as i16, center.y as i16, radius as i16, (10, 10, 10, 255));
+                list.circle(center.x as i16, center.y as i16, radius as i16, (255, 0, 255, 255));
+            }
+            Region::Box(r) => {
+                let x1 = r.up_left_corner.x as i16;
+                let y1 = r.up_left_corner.y as i16;
+                let x2 = (r.up_left_corner.x + r.width) as i16;
+                let y2 = (r.up_left_corner.y + r.height) as i16;
+                list.filled_rectangle(x1, y1, x2, y2, (255, 0, 255, 60));
+                list.rectangle(x1, y1, x2, y2, (255, 0, 255, 255));
+            }
+        }
+    }
+}
+impl PhysicProperty for KillZone {
+    fn update_particle(&self, p: &Particle, _time: f64) -> Particle {
+        let mut tmp = p.clone();
+        if tmp.is_alive() && self.region.contains(&p.get_position()) {
+            tmp.kill();
+            self.absorbed.fetch_add(1, Ordering::Relaxed);
+        }
+        tmp
+    }
+    fn as_drawable(&self) -> Option<&Drawable> {
+        Some(self)
+    }
+    fn name(&self) -> &str {
+        match self.region {
+            Region::Circle { .. } => "Sink",
+            Region::Box(_) => "Kill zone"
+        }
+    }
+    fn parameters(&self) -> Vec<Parameter> {
+        match self.region {
+            Region::Circle { radius, .. } => vec![Parameter::new("radius", radius, 1.0, 500.0, 1.0)],
+            Region::Box(_) => vec![]
+        }
+    }
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if let Region::Circle { ref mut radius, .. } = self.region {
+            if name == "radius" {
+                *radius = value;
+            }
+        }
+    }
+    fn absorbed(&self) -> Option<usize> {
+        Some(self.absorbed.load(Ordering::Relaxed))
+    }
+    fn contains(&self, p: &Point3) -> bool {
+        self.region.contains(p)
+    }
 }
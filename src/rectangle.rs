@@ -1,12 +1,6 @@
 
 use point3::Point3;
-use drawable::Drawable;
-
-use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-use sdl2::surface::Surface;
-use sdl2::gfx::primitives::DrawRenderer;
+use drawable::{Drawable, DrawList};
 
 #[derive(Debug, Default, PartialEq, Copy, Clone)]
 pub struct Rectangle {
@@ -36,18 +30,11 @@ impl Rectangle {
 }
 
 impl Drawable for Rectangle {
-    fn draw_surface(&self, c: &mut Canvas<Surface>) {
-        let r = Rect::new(self.up_left_corner.x as i32,
-                          self.up_left_corner.y as i32,
-                          self.width as u32,
-                          self.height as u32);
-        c.draw_rect(r).unwrap();
-    }
-    fn draw_window(&self, c: &mut Canvas<Window>) {
-        let r = Rect::new(self.up_left_corner.x as i32,
-                          self.up_left_corner.y as i32,
-                          self.width as u32,
-                          self.height as u32);
-        c.draw_rect(r).unwrap();
+    fn draw(&self, list: &mut DrawList) {
+        list.rectangle(self.up_left_corner.x as i16,
+                       self.up_left_corner.y as i16,
+                       (self.up_left_corner.x + self.width) as i16 - 1,
+                       (self.up_left_corner.y + self.height) as i16 - 1,
+                       (0, 0, 0, 255));
     }
 }
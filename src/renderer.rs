@@ -0,0 +1,13 @@
+use drawable::{Drawable, DrawList, Rgba};
+
+// Backend executing the draw lists: `sdl_render::SdlRenderer` for the SDL
+// canvases, `software_render::Framebuffer` to render without SDL
+pub trait Renderer {
+    fn clear(&mut self, color: Rgba);
+    fn render(&mut self, list: &DrawList);
+    fn draw(&mut self, d: &Drawable) {
+        let mut list = DrawList::new();
+        d.draw(&mut list);
+        self.render(&list);
+    }
+}
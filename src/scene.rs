@@ -0,0 +1,584 @@
+use particle::{Particle, ParticleBuilder, RenderMode};
+use blend::BlendMode;
+use sub_emitter::SubEmitter;
+use physic_property::{PhysicProperty, Gravity, GravityWell, AirResistance, Wind, Gust, BigGravityWell, Turbulence, Vortex, Attractor, KillZone};
+use vec3::Vec3;
+use collider::{Collider, Shape};
+use accelerator::{Accelerator, Zone};
+use point3::Point3;
+use rectangle::Rectangle;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Scene description, one element by line:
+//   bounds <x> <y> <width> <height>
+//   emitter <x> <y>
+//   gravity
+//   wind <force x> <force y> [gust sine|noise <amplitude> <period>] [spatial <amplitude> <wavelength>]
+//   air_resistance
+//   turbulence <frequency> <amplitude> <octaves> <evolution speed> [nocurl]
+//   gravity_well <x> <y> <strength> <area of effect>
+//   big_gravity_well <x> <y> <strength> <area of effect> <layers>
+//   vortex <x> <y> <radius> <strength> <falloff> <inward pull> [axis <x> <y> <z>]
+//   attractor <x> <y> <strength> <exponent> <softening> [<max range>]
+//   sink <x> <y> <radius>
+//   kill_zone <x> <y> <width> <height>
+//   tube <strength> <max speed> <radius> <x1> <y1> <x2> <y2> ...
+//   conveyor <strength> <max speed> <x> <y> <width> <height> <direction x> <direction y>
+//   funnel <strength> <max speed> <entry x> <entry y> <exit x> <exit y> <entry width> <exit width>
+//   circle_collider <restitution> <friction> <x> <y> <radius>
+//   box_collider <restitution> <friction> <x> <y> <width> <height> [<angle in degree>]
+//   segment_collider <restitution> <friction> <x1> <y1> <x2> <y2>
+//   polygon_collider <restitution> <friction> <x1> <y1> <x2> <y2> <x3> <y3> ...
+//   rope <x1> <y1> <x2> <y2> <segments> <stiffness> [pinned]
+//   spring <x1> <y1> <x2> <y2> <stiffness> <damping> [pinned]
+//   template <name> <radius> <lifetime> [color <r> <g> <b>] [trail <length>]
+//            [render circle|point|streak|sprite <index> <scale>]
+//            [blend alpha|additive|multiply|screen] [sub_emitter <name>]
+//   sub_emitter <name> <template> <count> <speed> [inherit_velocity] [inherit_color] [on_collision]
+//   spawn <template>
+// Templates and sub emitters must be defined before being used, the emitter
+// creates `spawn` with the direction of the default particles. The sprite
+// index is the position of the image in the --sprite options.
+// Empty lines and lines starting with '#' are ignored.
+pub struct Scene {
+    pub properties: Vec<Box<PhysicProperty>>,
+    pub boundaries: Rectangle,
+    pub colliders: Vec<Collider>,
+    pub emitter: Point3,
+    pub ropes: Vec<Rope>,
+    pub springs: Vec<Spring>,
+    pub sub_emitters: Vec<SubEmitter>,
+    pub spawn: Option<Particle>
+}
+
+// Chain of particles linked by distance constraints, see `World::add_rope`
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Rope {
+    pub start: Point3,
+    pub end: Point3,
+    pub segments: u32,
+    pub stiffness: f64,
+    pub pinned: bool // the first particle stays at `start`
+}
+
+// Two particles linked by a damped spring, at rest at their initial distance
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Spring {
+    pub a: Point3,
+    pub b: Point3,
+    pub stiffness: f64,
+    pub damping: f64,
+    pub pinned: bool // the first particle stays at `a`
+}
+
+fn parse_template(line: usize, args: &[&str], sub_emitters: &HashMap<String, usize>) -> Result<Particle, String> {
+    if args.len() < 2 {
+        return Err(format!("line {}: a template needs a radius and a lifetime", line));
+    }
+    let v = parse_args(line, &args[..2], 2)?;
+    let mut builder = ParticleBuilder::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+    builder.with_radius(v[0] as i16).with_lifetime(v[1].max(1.0) as u64);
+    let mut rest = &args[2..];
+    while !rest.is_empty() {
+        match rest[0] {
+            "color" if rest.len() >= 4 => {
+                let c = parse_args(line, &rest[1..4], 3)?;
+                builder.with_color(c[0] as u8, c[1] as u8, c[2] as u8);
+                rest = &rest[4..];
+            }
+            "trail" if rest.len() >= 2 => {
+                let t = parse_args(line, &rest[1..2], 1)?;
+                builder.with_trail(t[0].max(0.0) as u16);
+                rest = &rest[2..];
+            }
+            "render" if rest.len() >= 2 => {
+                let (mode, used) = match rest[1] {
+                    "circle" => (RenderMode::Circle, 2),
+                    "point" => (RenderMode::Point, 2),
+                    "streak" => (RenderMode::Streak, 2),
+                    "sprite" if rest.len() >= 4 => {
+                        let v = parse_args(line, &rest[2..4], 2)?;
+                        (RenderMode::Sprite { index: v[0] as usize, scale: v[1] }, 4)
+                    }
+                    m => return Err(format!("line {}: unknown render mode {}", line, m))
+                };
+                builder.with_render_mode(mode);
+                rest = &rest[used..];
+            }
+            "blend" if rest.len() >= 2 => {
+                match BlendMode::from_name(rest[1]) {
+                    Some(m) => builder.with_blend_mode(m),
+                    None => return Err(format!("line {}: unknown blend mode {}", line, rest[1]))
+                };
+                rest = &rest[2..];
+            }
+            "sub_emitter" if rest.len() >= 2 => {
+                match sub_emitters.get(rest[1]) {
+                    Some(&i) => builder.with_sub_emitter(i),
+                    None => return Err(format!("line {}: unknown sub emitter {}", line, rest[1]))
+                };
+                rest = &rest[2..];
+            }
+            k => return Err(format!("line {}: unexpected {}", line, k))
+        }
+    }
+    Ok(builder.create())
+}
+
+// Remove the optional `pinned` flag at the end of the arguments
+fn pinned<'a, 'b>(args: &'a [&'b str]) -> (&'a [&'b str], bool) {
+    match args.last() {
+        Some(&"pinned") => (&args[..args.len() - 1], true),
+        _ => (args, false)
+    }
+}
+
+fn parse_args(line: usize, args: &[&str], expected: usize) -> Result<Vec<f64>, String> {
+    if args.len() != expected {
+        return Err(format!("line {}: expected {} values, got {}", line, expected, args.len()));
+    }
+    args.iter()
+        .map(|a| a.parse::<f64>().map_err(|e| format!("line {}: {}: {}", line, a, e)))
+        .collect()
+}
+
+impl Scene {
+    pub fn parse(text: &str, default_bounds: Rectangle) -> Result<Scene, String> {
+        let mut scene = Scene {
+            properties: vec![],
+            boundaries: default_bounds,
+            colliders: vec![],
+            emitter: default_bounds.center(),
+            ropes: vec![],
+            springs: vec![],
+            sub_emitters: vec![],
+            spawn: None
+        };
+        let mut emitter_set = false;
+        let mut templates = HashMap::new();
+        let mut sub_emitters = HashMap::new();
+        for (i, l) in text.lines().enumerate() {
+            let line = i + 1;
+            let l = l.trim();
+            if l.is_empty() || l.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = l.split_whitespace().collect();
+            let args = &words[1..];
+            match words[0] {
+                "bounds" => {
+                    let v = parse_args(line, args, 4)?;
+                    scene.boundaries = Rectangle {
+                        up_left_corner: Point3::new(v[0], v[1], 0.0),
+                        width: v[2],
+                        height: v[3],
+                        depth: 0.0
+                    };
+                }
+                "emitter" => {
+                    let v = parse_args(line, args, 2)?;
+                    scene.emitter = Point3::new(v[0], v[1], 0.0);
+                    emitter_set = true;
+                }
+                "gravity" => {
+                    parse_args(line, args, 0)?;
+                    scene.properties.push(Box::new(Gravity{}));
+                }
+                "wind" => {
+                    if args.len() < 2 {
+                        return Err(format!("line {}: wind needs a force", line));
+                    }
+                    let v = parse_args(line, &args[..2], 2)?;
+                    let mut w = Wind::from_force(Vec3::new(v[0], v[1], 0.0));
+                    let mut rest = &args[2..];
+                    while !rest.is_empty() {
+                        match rest[0] {
+                            "gust" if rest.len() >= 4 => {
+                                let g = match rest[1] {
+                                    "sine" => Gust::Sine,
+                                    "noise" => Gust::Noise,
+                                    k => return Err(format!("line {}: unknown gust {}", line, k))
+                                };
+                                let v = parse_args(line, &rest[2..4], 2)?;
+                                w = w.with_gust(g, v[0], v[1]);
+                                rest = &rest[4..];
+                            }
+                            "spatial" if rest.len() >= 3 => {
+                                let v = parse_args(line, &rest[1..3], 2)?;
+                                w = w.with_spatial_variation(v[0], v[1]);
+                                rest = &rest[3..];
+                            }
+                            k => return Err(format!("line {}: unexpected {}", line, k))
+                        }
+                    }
+                    scene.properties.push(Box::new(w));
+                }
+                "turbulence" => {
+                    let curl = args.last() != Some(&"nocurl");
+                    let args = if curl { args } else { &args[..args.len() - 1] };
+                    let v = parse_args(line, args, 4)?;
+                    let mut t = Turbulence::new(v[0], v[1], v[2] as u32, v[3]);
+                    t.curl = curl;
+                    scene.properties.push(Box::new(t));
+                }
+                "air_resistance" => {
+                    parse_args(line, args, 0)?;
+                    scene.properties.push(Box::new(AirResistance::new()));
+                }
+                "gravity_well" => {
+                    let v = parse_args(line, args, 4)?;
+                    scene.properties.push(Box::new(GravityWell::new(Point3::new(v[0], v[1], 0.0),
+                                                                    v[2], v[3])));
+                }
+                "big_gravity_well" => {
+                    let v = parse_args(line, args, 5)?;
+                    scene.properties.push(Box::new(BigGravityWell::new(Point3::new(v[0], v[1], 0.0),
+                                                                       v[2], v[3], v[4] as u64)));
+                }
+                "vortex" => {
+                    if args.len() < 6 {
+                        return Err(format!("line {}: expected 6 values, got {}", line, args.len()));
+                    }
+                    let v = parse_args(line, &args[..6], 6)?;
+                    let mut vortex = Vortex::new(Point3::new(v[0], v[1], 0.0), v[2], v[3])
+                        .with_falloff(v[4])
+                        .with_inward_pull(v[5]);
+                    if args.len() > 6 {
+                        if args[6] != "axis" {
+                            return Err(format!("line {}: unexpected {}", line, args[6]));
+                        }
+                        let a = parse_args(line, &args[7..], 3)?;
+                        let axis = Vec3::new(a[0], a[1], a[2]);
+                        if axis.length() < 1e-6 {
+                            return Err(format!("line {}: the vortex axis can not be null", line));
+                        }
+                        vortex = vortex.with_axis(axis);
+                    }
+                    scene.properties.push(Box::new(vortex));
+                }
+                "attractor" => {
+                    let v = if args.len() == 6 { parse_args(line, args, 6)? } else { parse_args(line, args, 5)? };
+                    let mut a = Attractor::new(Point3::new(v[0], v[1], 0.0), v[2])
+                        .with_exponent(v[3])
+                        .with_softening(v[4]);
+                    if v.len() == 6 {
+                        a = a.with_max_range(v[5]);
+                    }
+                    scene.properties.push(Box::new(a));
+                }
+                "sink" => {
+                    let v = parse_args(line, args, 3)?;
+                    scene.properties.push(Box::new(KillZone::sink(Point3::new(v[0], v[1], 0.0), v[2])));
+                }
+                "kill_zone" => {
+                    let v = parse_args(line, args, 4)?;
+                    scene.properties.push(Box::new(KillZone::rectangle(Rectangle {
+                        up_left_corner: Point3::new(v[0], v[1], 0.0),
+                        width: v[2],
+                        height: v[3],
+                        depth: 0.0
+                    })));
+                }
+                "tube" => {
+                    if args.len() < 7 || args.len() % 2 != 1 {
+                        return Err(format!("line {}: tube needs at least 2 points", line));
+                    }
+                    let v = parse_args(line, args, args.len())?;
+                    let path = v[3..].chunks(2).map(|c| Point3::new(c[0], c[1], 0.0)).collect();
+                    scene.properties.push(Box::new(Accelerator::new(Zone::Tube { path: path, radius: v[2] },
+                                                                    v[0], v[1])));
+                }
+                "conveyor" => {
+                    let v = parse_args(line, args, 8)?;
+                    scene.properties.push(Box::new(Accelerator::new(Zone::Conveyor {
+                        area: Rectangle {
+                            up_left_corner: Point3::new(v[2], v[3], 0.0),
+                            width: v[4],
+                            height: v[5],
+                            depth: 0.0
+                        },
+                        direction: Vec3::new(v[6], v[7], 0.0)
+                    }, v[0], v[1])));
+                }
+                "funnel" => {
+                    let v = parse_args(line, args, 8)?;
+                    scene.properties.push(Box::new(Accelerator::new(Zone::Funnel {
+                        entry: Point3::new(v[2], v[3], 0.0),
+                        exit: Point3::new(v[4], v[5], 0.0),
+                        entry_width: v[6],
+                        exit_width: v[7]
+                    }, v[0], v[1])));
+                }
+                "circle_collider" => {
+                    let v = parse_args(line, args, 5)?;
+                    scene.colliders.push(Collider::new(Shape::Circle {
+                        center: Point3::new(v[2], v[3], 0.0),
+                        radius: v[4]
+                    }).with_restitution(v[0]).with_friction(v[1]));
+                }
+                "box_collider" => {
+                    let v = if args.len() == 7 { parse_args(line, args, 7)? } else { parse_args(line, args, 6)? };
+                    scene.colliders.push(Collider::new(Shape::Box {
+                        center: Point3::new(v[2], v[3], 0.0),
+                        width: v[4],
+                        height: v[5],
+                        angle: if v.len() == 7 { v[6].to_radians() } else { 0.0 }
+                    }).with_restitution(v[0]).with_friction(v[1]));
+                }
+                "segment_collider" => {
+                    let v = parse_args(line, args, 6)?;
+                    scene.colliders.push(Collider::new(Shape::Segment {
+                        a: Point3::new(v[2], v[3], 0.0),
+                        b: Point3::new(v[4], v[5], 0.0)
+                    }).with_restitution(v[0]).with_friction(v[1]));
+                }
+                "polygon_collider" => {
+                    if args.len() < 8 || args.len() % 2 != 0 {
+                        return Err(format!("line {}: polygon needs at least 3 points", line));
+                    }
+                    let v = parse_args(line, args, args.len())?;
+                    let points = v[2..].chunks(2).map(|c| Point3::new(c[0], c[1], 0.0)).collect();
+                    scene.colliders.push(Collider::new(Shape::Polygon { points: points })
+                                         .with_restitution(v[0]).with_friction(v[1]));
+                }
+                "rope" => {
+                    let (args, pinned) = pinned(args);
+                    let v = parse_args(line, args, 6)?;
+                    if v[4] < 1.0 {
+                        return Err(format!("line {}: a rope needs at least one segment", line));
+                    }
+                    scene.ropes.push(Rope {
+                        start: Point3::new(v[0], v[1], 0.0),
+                        end: Point3::new(v[2], v[3], 0.0),
+                        segments: v[4] as u32,
+                        stiffness: v[5],
+                        pinned: pinned
+                    });
+                }
+                "spring" => {
+                    let (args, pinned) = pinned(args);
+                    let v = parse_args(line, args, 6)?;
+                    scene.springs.push(Spring {
+                        a: Point3::new(v[0], v[1], 0.0),
+                        b: Point3::new(v[2], v[3], 0.0),
+                        stiffness: v[4],
+                        damping: v[5],
+                        pinned: pinned
+                    });
+                }
+                "template" => {
+                    if args.is_empty() {
+                        return Err(format!("line {}: a template needs a name", line));
+                    }
+                    let t = parse_template(line, &args[1..], &sub_emitters)?;
+                    templates.insert(args[0].to_string(), t);
+                }
+                "sub_emitter" => {
+                    if args.len() < 4 {
+                        return Err(format!("line {}: expected a name, a template, a count and a speed", line));
+                    }
+                    let template = match templates.get(args[1]) {
+                        Some(&t) => t,
+                        None => return Err(format!("line {}: unknown template {}", line, args[1]))
+                    };
+                    let v = parse_args(line, &args[2..4], 2)?;
+                    let mut se = SubEmitter::new(template, v[0] as u32, v[1]);
+                    for flag in &args[4..] {
+                        se = match *flag {
+                            "inherit_velocity" => se.inherit_velocity(),
+                            "inherit_color" => se.inherit_color(),
+                            "on_collision" => se.on_collision(),
+                            k => return Err(format!("line {}: unexpected {}", line, k))
+                        };
+                    }
+                    sub_emitters.insert(args[0].to_string(), scene.sub_emitters.len());
+                    scene.sub_emitters.push(se);
+                }
+                "spawn" => {
+                    if args.len() != 1 {
+                        return Err(format!("line {}: expected a template", line));
+                    }
+                    match templates.get(args[0]) {
+                        Some(&t) => scene.spawn = Some(t),
+                        None => return Err(format!("line {}: unknown template {}", line, args[0]))
+                    }
+                }
+                w => return Err(format!("line {}: unknown element {}", line, w))
+            }
+        }
+        if !emitter_set {
+            scene.emitter = scene.boundaries.center();
+        }
+        Ok(scene)
+    }
+
+    pub fn load(path: &Path, default_bounds: Rectangle) -> Result<Scene, String> {
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut text))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Scene::parse(&text, default_bounds)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// Poll the modification time of a scene file and reload it when it changes
+pub struct SceneWatcher {
+    path: PathBuf,
+    default_bounds: Rectangle,
+    last_modified: Option<SystemTime>
+}
+
+impl SceneWatcher {
+    pub fn new(path: &Path, default_bounds: Rectangle) -> SceneWatcher {
+        SceneWatcher {
+            path: path.to_path_buf(),
+            default_bounds: default_bounds,
+            last_modified: SceneWatcher::modified(path)
+        }
+    }
+
+    // Bounds used by the scenes without a `bounds` line
+    pub fn set_default_bounds(&mut self, b: Rectangle) {
+        self.default_bounds = b;
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        path.metadata().and_then(|m| m.modified()).ok()
+    }
+
+    // Return the new scene if the file changed since the last call
+    pub fn poll(&mut self) -> Option<Result<Scene, String>> {
+        let modified = SceneWatcher::modified(&self.path);
+        if modified.is_none() || modified == self.last_modified {
+            return None;
+        }
+        self.last_modified = modified;
+        Some(Scene::load(&self.path, self.default_bounds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> Rectangle {
+        Rectangle {
+            up_left_corner: Point3::new(0.0, 0.0, 0.0),
+            width: 800.0,
+            height: 600.0,
+            depth: 0.0
+        }
+    }
+
+    fn error(text: &str) -> String {
+        Scene::parse(text, bounds()).err().expect("the scene should not parse")
+    }
+
+    #[test]
+    fn empty_scene() {
+        let scene = Scene::parse("# nothing\n\n   \n", bounds()).unwrap();
+        assert_eq!(scene.boundaries, bounds());
+        assert_eq!(scene.emitter, Point3::new(400.0, 300.0, 0.0));
+        assert!(scene.properties.is_empty() && scene.colliders.is_empty());
+    }
+
+    #[test]
+    fn emitter_defaults_to_the_center_of_the_bounds() {
+        let scene = Scene::parse("bounds 100 100 200 50", bounds()).unwrap();
+        assert_eq!(scene.emitter, Point3::new(200.0, 125.0, 0.0));
+        let scene = Scene::parse("emitter 5 6\nbounds 100 100 200 50", bounds()).unwrap();
+        assert_eq!(scene.emitter, Point3::new(5.0, 6.0, 0.0));
+    }
+
+    #[test]
+    fn elements() {
+        let text = "gravity\n\
+                    wind -0.25 0 gust sine 0.5 100\n\
+                    box_collider 0.5 0.1 10 20 30 40 90\n\
+                    segment_collider 1 0 0 0 10 0";
+        let scene = Scene::parse(text, bounds()).unwrap();
+        let names: Vec<&str> = scene.properties.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["Gravity", "Wind"]);
+        assert_eq!(scene.colliders.len(), 2);
+        assert_eq!(scene.colliders[0].shape, Shape::Box {
+            center: Point3::new(10.0, 20.0, 0.0),
+            width: 30.0,
+            height: 40.0,
+            angle: 90.0f64.to_radians()
+        });
+        assert_eq!(scene.colliders[1].restitution, 1.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(error("gravity\nfoo 1 2").starts_with("line 2: unknown element foo"));
+        assert!(error("gravity_well 1 2 3").starts_with("line 1: expected 4 values, got 3"));
+        assert!(error("bounds 0 0 a 10").starts_with("line 1: a:"));
+    }
+
+    #[test]
+    fn vortex_axis() {
+        let scene = Scene::parse("vortex 100 100 50 2 1 0.5 axis 0 0 -2", bounds()).unwrap();
+        let axis_z = scene.properties[0].parameters().into_iter().find(|p| p.name == "axis_z").unwrap();
+        assert_eq!(axis_z.value, -1.0);
+        assert!(error("vortex 1 1 10 1 1 0 axis 0 0 0").contains("null"));
+    }
+
+    #[test]
+    fn ropes_and_springs() {
+        let scene = Scene::parse("rope 10 10 10 50 4 1 pinned\nspring 0 0 30 40 0.5 0.1", bounds()).unwrap();
+        assert_eq!(scene.ropes, vec![Rope {
+            start: Point3::new(10.0, 10.0, 0.0),
+            end: Point3::new(10.0, 50.0, 0.0),
+            segments: 4,
+            stiffness: 1.0,
+            pinned: true
+        }]);
+        assert_eq!(scene.springs.len(), 1);
+        assert!(!scene.springs[0].pinned);
+        assert!(error("rope 0 0 10 10 0 1").contains("segment"));
+    }
+
+    #[test]
+    fn templates_and_sub_emitters() {
+        let text = "template spark 2 30 color 255 0 0\n\
+                    sub_emitter burst spark 10 3 inherit_color on_collision\n\
+                    template rocket 4 100 sub_emitter burst\n\
+                    spawn rocket";
+        assert!(Scene::parse("", bounds()).unwrap().spawn.is_none());
+        let scene = Scene::parse(text, bounds()).unwrap();
+        let rocket = scene.spawn.unwrap();
+        assert_eq!(rocket.get_radius(), 4);
+        assert_eq!(rocket.get_lifetime(), 100);
+        assert_eq!(rocket.get_sub_emitter(), Some(0));
+        let burst = &scene.sub_emitters[0];
+        assert_eq!((burst.count, burst.speed), (10, 3.0));
+        assert!(burst.inherit_color && burst.on_collision && !burst.inherit_velocity);
+        assert_eq!(burst.template.get_color(), Some((255, 0, 0)));
+        assert!(error("spawn rocket").contains("unknown template rocket"));
+        assert!(error("template rocket 4 100 sub_emitter burst").contains("unknown sub emitter burst"));
+    }
+
+    #[test]
+    fn template_trail() {
+        let scene = Scene::parse("template comet 3 50 trail 5\nspawn comet", bounds()).unwrap();
+        assert_eq!(scene.spawn.unwrap().get_trail_length(), 5);
+    }
+
+    #[test]
+    fn template_render_and_blend_modes() {
+        let scene = Scene::parse("template rocket 4 100 render sprite 1 0.5 blend additive\nspawn rocket",
+                                 bounds()).unwrap();
+        let rocket = scene.spawn.unwrap();
+        assert_eq!(rocket.get_render_mode(), RenderMode::Sprite { index: 1, scale: 0.5 });
+        assert_eq!(rocket.get_blend_mode(), BlendMode::Additive);
+        assert!(error("template spark 2 30 render cube").contains("unknown render mode cube"));
+        assert!(error("template spark 2 30 blend darken").contains("unknown blend mode darken"));
+    }
+}
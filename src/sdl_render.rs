@@ -0,0 +1,353 @@
+use drawable::{DrawCommand, DrawList, Rgba};
+use blend::{BlendMode, mix};
+use renderer::Renderer;
+use software_render::{Framebuffer, polygon_spans, thick_line_polygon, to_f64};
+use sprite::Sprites;
+
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{self, Canvas, RenderTarget, SurfaceContext, Texture, TextureCreator};
+use sdl2::surface::Surface;
+
+use std::mem;
+
+// SDL blend mode and color to use for a blend mode. SDL has no screen mode,
+// see `screen_passes`, only the sprites fall back to additive.
+fn sdl_blend(mode: BlendMode, color: Rgba) -> (render::BlendMode, Color) {
+    let (r, g, b, a) = color;
+    match mode {
+        BlendMode::Alpha => (render::BlendMode::Blend, Color::RGBA(r, g, b, a)),
+        BlendMode::Additive | BlendMode::Screen => (render::BlendMode::Add, Color::RGBA(r, g, b, a)),
+        // SDL modulate ignore the alpha, fade toward white instead
+        BlendMode::Multiply => (render::BlendMode::Mod,
+                                Color::RGB(mix(255, r, a), mix(255, g, a), mix(255, b, a)))
+    }
+}
+
+// Screen is dst + a * src * (1 - dst) = dst * (1 - a * src) + a * src: a
+// modulate by (1 - a * src) followed by an addition of a * src
+fn screen_passes(color: Rgba) -> (Color, Color) {
+    let (r, g, b, a) = color;
+    let s = |c: u8| (c as u32 * a as u32 / 255) as u8;
+    (Color::RGB(255 - s(r), 255 - s(g), 255 - s(b)),
+     Color::RGBA(s(r), s(g), s(b), 255))
+}
+
+// SDL_gfx always use the alpha blending, the other modes go through the SDL
+// primitives. The screen mode draws the shape twice.
+fn with_blend<T: RenderTarget, F: Fn(&mut Canvas<T>)>(c: &mut Canvas<T>, mode: BlendMode,
+                                                       color: Rgba, f: F) {
+    let previous = c.blend_mode();
+    if mode == BlendMode::Screen {
+        let (modulate, add) = screen_passes(color);
+        c.set_blend_mode(render::BlendMode::Mod);
+        c.set_draw_color(modulate);
+        f(c);
+        c.set_blend_mode(render::BlendMode::Add);
+        c.set_draw_color(add);
+        f(c);
+    } else {
+        let (sdl_mode, color) = sdl_blend(mode, color);
+        c.set_blend_mode(sdl_mode);
+        c.set_draw_color(color);
+        f(c);
+    }
+    c.set_blend_mode(previous);
+}
+
+fn fill_circle<T: RenderTarget>(c: &mut Canvas<T>, x: i16, y: i16, rad: i16) {
+    let rad = rad as i32;
+    for dy in -rad..(rad + 1) {
+        let half = ((rad * rad - dy * dy) as f64).sqrt() as i32;
+        c.fill_rect(Rect::new(x as i32 - half, y as i32 + dy, (2 * half + 1) as u32, 1)).unwrap();
+    }
+}
+
+fn fill_polygon<T: RenderTarget>(c: &mut Canvas<T>, xs: &[f64], ys: &[f64]) {
+    let height = c.output_size().unwrap().1 as i32;
+    polygon_spans(xs, ys, height, |x1, x2, y| if x2 >= x1 {
+        c.fill_rect(Rect::new(x1, y, (x2 - x1 + 1) as u32, 1)).unwrap();
+    });
+}
+
+fn draw_sprite<T: RenderTarget>(c: &mut Canvas<T>, texture: &mut Texture, x: f64, y: f64, scale: f64,
+                                angle: f64, color: Rgba, blend: BlendMode) {
+    let (r, g, b, a) = color;
+    texture.set_color_mod(r, g, b);
+    texture.set_alpha_mod(a);
+    // the two passes of the screen mode would need an inverted copy of the
+    // texture, the sprites are drawn as additive instead (close to screen on
+    // dark backgrounds, brighter on light ones)
+    texture.set_blend_mode(match blend {
+        BlendMode::Alpha => render::BlendMode::Blend,
+        BlendMode::Additive | BlendMode::Screen => render::BlendMode::Add,
+        BlendMode::Multiply => render::BlendMode::Mod
+    });
+    let q = texture.query();
+    let w = ((q.width as f64) * scale).max(1.0) as u32;
+    let h = ((q.height as f64) * scale).max(1.0) as u32;
+    c.copy_ex(texture, None,
+              Some(Rect::new(x as i32 - (w / 2) as i32, y as i32 - (h / 2) as i32, w, h)),
+              angle, None, false, false).unwrap();
+}
+
+// Execute the commands on any SDL canvas (window or surface), the sprites
+// without texture are drawn as circles
+pub fn render<T: RenderTarget>(list: &DrawList, c: &mut Canvas<T>, textures: &mut [Texture]) {
+    for cmd in &list.commands {
+        match *cmd {
+            DrawCommand::Pixel { x, y, color, blend: BlendMode::Alpha } => {
+                c.pixel(x, y, color).unwrap();
+            }
+            DrawCommand::Pixel { x, y, color, blend } => {
+                with_blend(c, blend, color, |c| c.draw_point((x as i32, y as i32)).unwrap());
+            }
+            DrawCommand::Line { x1, y1, x2, y2, color, blend: BlendMode::Alpha } => {
+                c.line(x1, y1, x2, y2, color).unwrap();
+            }
+            DrawCommand::Line { x1, y1, x2, y2, color, blend } => {
+                with_blend(c, blend, color,
+                           |c| c.draw_line((x1 as i32, y1 as i32), (x2 as i32, y2 as i32)).unwrap());
+            }
+            DrawCommand::AaLine { x1, y1, x2, y2, color, blend: BlendMode::Alpha } => {
+                c.aa_line(x1, y1, x2, y2, color).unwrap();
+            }
+            DrawCommand::AaLine { x1, y1, x2, y2, color, blend } => {
+                with_blend(c, blend, color,
+                           |c| c.draw_line((x1 as i32, y1 as i32), (x2 as i32, y2 as i32)).unwrap());
+            }
+            DrawCommand::ThickLine { x1, y1, x2, y2, width, color, blend: BlendMode::Alpha } => {
+                c.thick_line(x1, y1, x2, y2, width, color).unwrap();
+            }
+            DrawCommand::ThickLine { x1, y1, x2, y2, width, color, blend } => {
+                match thick_line_polygon(x1 as i32, y1 as i32, x2 as i32, y2 as i32, width) {
+                    Some((xs, ys)) => with_blend(c, blend, color, |c| fill_polygon(c, &xs, &ys)),
+                    None => with_blend(c, blend, color,
+                                       |c| c.draw_line((x1 as i32, y1 as i32), (x2 as i32, y2 as i32)).unwrap())
+                }
+            }
+            DrawCommand::Circle { x, y, radius, color } => {
+                c.circle(x, y, radius, color).unwrap();
+            }
+            DrawCommand::FilledCircle { x, y, radius, color, blend: BlendMode::Alpha } => {
+                c.filled_circle(x, y, radius, color).unwrap();
+            }
+            DrawCommand::FilledCircle { x, y, radius, color, blend } => {
+                with_blend(c, blend, color, |c| fill_circle(c, x, y, radius));
+            }
+            DrawCommand::Rectangle { x1, y1, x2, y2, color } => {
+                c.rectangle(x1, y1, x2, y2, color).unwrap();
+            }
+            DrawCommand::FilledRectangle { x1, y1, x2, y2, color } => {
+                c.box_(x1, y1, x2, y2, color).unwrap();
+            }
+            DrawCommand::Polygon { ref xs, ref ys, color } => {
+                c.polygon(xs, ys, color).unwrap();
+            }
+            DrawCommand::FilledPolygon { ref xs, ref ys, color, blend: BlendMode::Alpha } => {
+                c.filled_polygon(xs, ys, color).unwrap();
+            }
+            DrawCommand::FilledPolygon { ref xs, ref ys, color, blend } => {
+                let (xs, ys) = (to_f64(xs), to_f64(ys));
+                with_blend(c, blend, color, |c| fill_polygon(c, &xs, &ys));
+            }
+            DrawCommand::FilledTrigon { x1, y1, x2, y2, x3, y3, color } => {
+                c.filled_trigon(x1, y1, x2, y2, x3, y3, color).unwrap();
+            }
+            DrawCommand::Sprite { index, x, y, scale, angle, color, blend } => {
+                match textures.get_mut(index) {
+                    Some(t) => draw_sprite(c, t, x, y, scale, angle, color, blend),
+                    None => c.filled_circle(x as i16, y as i16, 3, color).unwrap()
+                }
+            }
+        }
+    }
+}
+
+pub struct SdlRenderer<'a, 'r: 'a, T: RenderTarget + 'a> {
+    canvas: &'a mut Canvas<T>,
+    textures: &'a mut [Texture<'r>]
+}
+
+impl<'a, 'r, T: RenderTarget> SdlRenderer<'a, 'r, T> {
+    pub fn new(canvas: &'a mut Canvas<T>, textures: &'a mut [Texture<'r>]) -> SdlRenderer<'a, 'r, T> {
+        SdlRenderer {
+            canvas: canvas,
+            textures: textures
+        }
+    }
+}
+
+impl<'a, 'r, T: RenderTarget> Renderer for SdlRenderer<'a, 'r, T> {
+    fn clear(&mut self, color: Rgba) {
+        self.canvas.set_draw_color(Color::RGBA(color.0, color.1, color.2, color.3));
+        self.canvas.clear();
+    }
+    fn render(&mut self, list: &DrawList) {
+        render(list, self.canvas, self.textures);
+    }
+}
+
+// Format whose bytes are R, G, B, A in memory whatever the endianness
+pub fn rgba_bytes_format() -> PixelFormatEnum {
+    if cfg!(target_endian = "little") {
+        PixelFormatEnum::ABGR8888
+    } else {
+        PixelFormatEnum::RGBA8888
+    }
+}
+
+// Copy of a software frame, to display it with SDL
+pub fn framebuffer_to_surface(fb: &Framebuffer) -> Surface<'static> {
+    let mut surface = Surface::new(fb.width(), fb.height(), rgba_bytes_format()).unwrap();
+    let pitch = surface.pitch() as usize;
+    let row = (fb.width() * 4) as usize;
+    surface.with_lock_mut(|pixels: &mut [u8]| {
+        for (y, src) in fb.pixels().chunks(row).enumerate() {
+            pixels[y * pitch..y * pitch + row].copy_from_slice(src);
+        }
+    });
+    surface
+}
+
+// Pixels of a surface as R, G, B, A bytes, rows from the top without padding
+pub fn surface_to_rgba(surface: &Surface) -> Vec<u8> {
+    let converted = surface.convert_format(rgba_bytes_format()).unwrap();
+    let pitch = converted.pitch() as usize;
+    let row = (converted.width() * 4) as usize;
+    let mut rgba = Vec::with_capacity(row * converted.height() as usize);
+    converted.with_lock(|pixels: &[u8]| {
+        for y in 0..converted.height() as usize {
+            rgba.extend_from_slice(&pixels[y * pitch..y * pitch + row]);
+        }
+    });
+    rgba
+}
+
+// Surface canvas and the sprite textures of its renderer, created once
+pub struct SdlTarget {
+    // declared first to be dropped before the creator and the canvas
+    textures: Vec<Texture<'static>>,
+    _creator: TextureCreator<SurfaceContext<'static>>,
+    canvas: Canvas<Surface<'static>>
+}
+
+impl SdlTarget {
+    fn new(width: u32, height: u32, sprites: &Sprites) -> SdlTarget {
+        let surface = Surface::new(width, height, PixelFormatEnum::RGBA8888).unwrap();
+        let canvas = Canvas::from_surface(surface).unwrap();
+        let creator = canvas.texture_creator();
+        // the textures only borrow the creator to stay behind the renderer,
+        // which the struct keeps alive until they are dropped
+        let textures: Vec<Texture<'static>> = unsafe { mem::transmute(sprites.textures(&creator)) };
+        SdlTarget {
+            textures: textures,
+            _creator: creator,
+            canvas: canvas
+        }
+    }
+}
+
+// Image the world is rendered in before being shown or exported, with SDL or
+// the software renderer. Meant to be kept between the frames, and recreated
+// when the size changes.
+pub enum Offscreen {
+    Sdl(SdlTarget),
+    Software(Framebuffer)
+}
+
+impl Offscreen {
+    pub fn new(width: u32, height: u32, software: bool, sprites: &Sprites) -> Offscreen {
+        if software {
+            Offscreen::Software(Framebuffer::new(width, height))
+        } else {
+            Offscreen::Sdl(SdlTarget::new(width, height, sprites))
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        match *self {
+            Offscreen::Sdl(ref t) => (t.canvas.surface().width(), t.canvas.surface().height()),
+            Offscreen::Software(ref fb) => (fb.width(), fb.height())
+        }
+    }
+
+    // Format of the pixels given by `upload`
+    pub fn format(&self) -> PixelFormatEnum {
+        match *self {
+            Offscreen::Sdl(_) => PixelFormatEnum::RGBA8888,
+            Offscreen::Software(_) => rgba_bytes_format()
+        }
+    }
+
+    pub fn render(&mut self, list: &DrawList) {
+        match *self {
+            Offscreen::Sdl(ref mut t) => {
+                let mut renderer = SdlRenderer::new(&mut t.canvas, &mut t.textures);
+                renderer.clear((0, 0, 0, 255));
+                renderer.render(list);
+            }
+            Offscreen::Software(ref mut fb) => {
+                fb.clear((0, 0, 0, 255));
+                fb.render(list);
+            }
+        }
+    }
+
+    // Copy the image in a streaming texture of the same size and format
+    pub fn upload(&self, texture: &mut Texture) {
+        match *self {
+            Offscreen::Sdl(ref t) => {
+                let surface = t.canvas.surface();
+                let pitch = surface.pitch() as usize;
+                surface.with_lock(|pixels: &[u8]| texture.update(None, pixels, pitch).unwrap());
+            }
+            Offscreen::Software(ref fb) =>
+                texture.update(None, fb.pixels(), (fb.width() * 4) as usize).unwrap()
+        }
+    }
+
+    // Copy of the image, the offscreen can be rendered again
+    pub fn to_surface(&self) -> Surface<'static> {
+        match *self {
+            Offscreen::Sdl(ref t) => t.canvas.surface().convert_format(PixelFormatEnum::RGBA8888).unwrap(),
+            Offscreen::Software(ref fb) => framebuffer_to_surface(fb)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blend::blend_pixel;
+
+    // what SDL does with the two passes on one channel
+    fn sdl_screen(dst: u8, color: Rgba, channel: usize) -> u8 {
+        let (modulate, add) = screen_passes(color);
+        let (m, s) = match channel {
+            0 => (modulate.r, add.r),
+            1 => (modulate.g, add.g),
+            _ => (modulate.b, add.b)
+        };
+        let d = dst as u32 * m as u32 / 255;
+        ::std::cmp::min(255, d + s as u32 * add.a as u32 / 255) as u8
+    }
+
+    #[test]
+    fn screen_passes_match_the_software_screen() {
+        let colors = [(255, 0, 0, 255), (128, 64, 200, 255), (200, 100, 50, 128), (10, 250, 90, 30)];
+        for &dst in &[0, 1, 60, 128, 200, 255] {
+            for &color in &colors {
+                let expected = blend_pixel(BlendMode::Screen, (dst, dst, dst), color);
+                let expected = [expected.0, expected.1, expected.2];
+                for channel in 0..3 {
+                    let got = sdl_screen(dst, color, channel);
+                    let diff = (got as i32 - expected[channel] as i32).abs();
+                    assert!(diff <= 2, "dst {} color {:?} channel {}: {} != {}",
+                            dst, color, channel, got, expected[channel]);
+                }
+            }
+        }
+    }
+}
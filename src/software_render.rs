@@ -0,0 +1,345 @@
+use drawable::{DrawCommand, DrawList, Rgba};
+use blend::{BlendMode, blend_pixel};
+use renderer::Renderer;
+
+use std::cmp::{min, max};
+
+// RGBA image in memory, 4 bytes by pixel, rows from the top. Everything is
+// rasterized here, so frames can be produced (and compared) without a display.
+// The antialiased lines are drawn aliased and the sprites (which need SDL to
+// be loaded) as small circles.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            width: width,
+            height: height,
+            pixels: vec![0; (width * height * 4) as usize]
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Rgba {
+        let i = ((y * self.width + x) * 4) as usize;
+        (self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3])
+    }
+
+    fn put(&mut self, x: i32, y: i32, color: Rgba, blend: BlendMode) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let i = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let (r, g, b) = blend_pixel(blend,
+                                    (self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]),
+                                    color);
+        let a = self.pixels[i + 3] as u32;
+        self.pixels[i] = r;
+        self.pixels[i + 1] = g;
+        self.pixels[i + 2] = b;
+        self.pixels[i + 3] = (color.3 as u32 + a * (255 - color.3 as u32) / 255) as u8;
+    }
+
+    fn span(&mut self, x1: i32, x2: i32, y: i32, color: Rgba, blend: BlendMode) {
+        for x in max(0, x1)..(min(self.width as i32 - 1, x2) + 1) {
+            self.put(x, y, color, blend);
+        }
+    }
+
+    // Bresenham
+    fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Rgba, blend: BlendMode) {
+        let dx = (x2 - x1).abs();
+        let dy = -(y2 - y1).abs();
+        let sx = if x1 < x2 { 1 } else { -1 };
+        let sy = if y1 < y2 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let (mut x, mut y) = (x1, y1);
+        loop {
+            self.put(x, y, color, blend);
+            if x == x2 && y == y2 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn filled_circle(&mut self, x: i32, y: i32, rad: i32, color: Rgba, blend: BlendMode) {
+        for dy in -rad..(rad + 1) {
+            let half = ((rad * rad - dy * dy) as f64).sqrt() as i32;
+            self.span(x - half, x + half, y + dy, color, blend);
+        }
+    }
+
+    // Midpoint circle
+    fn circle(&mut self, cx: i32, cy: i32, rad: i32, color: Rgba) {
+        let mut x = rad;
+        let mut y = 0;
+        let mut err = 1 - rad;
+        while x >= y {
+            let mut points = vec![(x, y), (y, x), (-y, x), (-x, y),
+                                  (-x, -y), (-y, -x), (y, -x), (x, -y)];
+            // do not blend twice the same pixel on the diagonals and the axes
+            points.sort();
+            points.dedup();
+            for (px, py) in points {
+                self.put(cx + px, cy + py, color, BlendMode::Alpha);
+            }
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    fn filled_polygon(&mut self, xs: &[f64], ys: &[f64], color: Rgba, blend: BlendMode) {
+        let height = self.height as i32;
+        polygon_spans(xs, ys, height, |x1, x2, y| self.span(x1, x2, y, color, blend));
+    }
+
+    fn thick_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, width: u8, color: Rgba, blend: BlendMode) {
+        match thick_line_polygon(x1, y1, x2, y2, width) {
+            Some((xs, ys)) => self.filled_polygon(&xs, &ys, color, blend),
+            None => self.line(x1, y1, x2, y2, color, blend)
+        }
+    }
+}
+
+// Scanline fill of a polygon, sampled at the center of the pixels: `span` is
+// called with the first and last pixel of each row, from 0 to `height`
+pub fn polygon_spans<F: FnMut(i32, i32, i32)>(xs: &[f64], ys: &[f64], height: i32, mut span: F) {
+    let n = xs.len();
+    if n < 3 {
+        return;
+    }
+    let min_y = ys.iter().cloned().fold(::std::f64::MAX, f64::min).floor() as i32;
+    let max_y = ys.iter().cloned().fold(::std::f64::MIN, f64::max).ceil() as i32;
+    for y in max(0, min_y)..min(height, max_y + 1) {
+        let sy = y as f64 + 0.5;
+        let mut nodes = vec![];
+        for i in 0..n {
+            let j = (i + 1) % n;
+            if (ys[i] <= sy && ys[j] > sy) || (ys[j] <= sy && ys[i] > sy) {
+                nodes.push(xs[i] + (sy - ys[i]) / (ys[j] - ys[i]) * (xs[j] - xs[i]));
+            }
+        }
+        nodes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for pair in nodes.chunks(2) {
+            if pair.len() == 2 {
+                span((pair[0] - 0.5).ceil() as i32, (pair[1] - 0.5).floor() as i32, y);
+            }
+        }
+    }
+}
+
+// Quad covered by a thick line, None when it is drawn as a simple line
+pub fn thick_line_polygon(x1: i32, y1: i32, x2: i32, y2: i32, width: u8) -> Option<([f64; 4], [f64; 4])> {
+    let dx = (x2 - x1) as f64;
+    let dy = (y2 - y1) as f64;
+    let len = (dx * dx + dy * dy).sqrt();
+    if width <= 1 || len == 0.0 {
+        return None;
+    }
+    let (nx, ny) = (-dy / len * width as f64 / 2.0, dx / len * width as f64 / 2.0);
+    let (x1, y1, x2, y2) = (x1 as f64 + 0.5, y1 as f64 + 0.5, x2 as f64 + 0.5, y2 as f64 + 0.5);
+    Some(([x1 + nx, x2 + nx, x2 - nx, x1 - nx],
+          [y1 + ny, y2 + ny, y2 - ny, y1 - ny]))
+}
+
+pub fn to_f64(v: &[i16]) -> Vec<f64> {
+    v.iter().map(|&x| x as f64 + 0.5).collect()
+}
+
+impl Renderer for Framebuffer {
+    fn clear(&mut self, color: Rgba) {
+        for p in self.pixels.chunks_mut(4) {
+            p[0] = color.0;
+            p[1] = color.1;
+            p[2] = color.2;
+            p[3] = color.3;
+        }
+    }
+
+    fn render(&mut self, list: &DrawList) {
+        for cmd in &list.commands {
+            match *cmd {
+                DrawCommand::Pixel { x, y, color, blend } => self.put(x as i32, y as i32, color, blend),
+                DrawCommand::Line { x1, y1, x2, y2, color, blend } =>
+                    self.line(x1 as i32, y1 as i32, x2 as i32, y2 as i32, color, blend),
+                DrawCommand::AaLine { x1, y1, x2, y2, color, blend } =>
+                    self.line(x1 as i32, y1 as i32, x2 as i32, y2 as i32, color, blend),
+                DrawCommand::ThickLine { x1, y1, x2, y2, width, color, blend } =>
+                    self.thick_line(x1 as i32, y1 as i32, x2 as i32, y2 as i32, width, color, blend),
+                DrawCommand::Circle { x, y, radius, color } =>
+                    self.circle(x as i32, y as i32, radius as i32, color),
+                DrawCommand::FilledCircle { x, y, radius, color, blend } =>
+                    self.filled_circle(x as i32, y as i32, radius as i32, color, blend),
+                DrawCommand::Rectangle { x1, y1, x2, y2, color } => {
+                    let (x1, y1, x2, y2) = (x1 as i32, y1 as i32, x2 as i32, y2 as i32);
+                    self.span(x1, x2, y1, color, BlendMode::Alpha);
+                    if y2 != y1 {
+                        self.span(x1, x2, y2, color, BlendMode::Alpha);
+                    }
+                    for y in (min(y1, y2) + 1)..max(y1, y2) {
+                        self.put(x1, y, color, BlendMode::Alpha);
+                        if x2 != x1 {
+                            self.put(x2, y, color, BlendMode::Alpha);
+                        }
+                    }
+                }
+                DrawCommand::FilledRectangle { x1, y1, x2, y2, color } => {
+                    for y in min(y1, y2)..(max(y1, y2) + 1) {
+                        self.span(min(x1, x2) as i32, max(x1, x2) as i32, y as i32, color, BlendMode::Alpha);
+                    }
+                }
+                DrawCommand::Polygon { ref xs, ref ys, color } => {
+                    let n = min(xs.len(), ys.len());
+                    for i in 0..n {
+                        let j = (i + 1) % n;
+                        self.line(xs[i] as i32, ys[i] as i32, xs[j] as i32, ys[j] as i32,
+                                  color, BlendMode::Alpha);
+                    }
+                }
+                DrawCommand::FilledPolygon { ref xs, ref ys, color, blend } =>
+                    self.filled_polygon(&to_f64(xs), &to_f64(ys), color, blend),
+                DrawCommand::FilledTrigon { x1, y1, x2, y2, x3, y3, color } =>
+                    self.filled_polygon(&to_f64(&[x1, x2, x3]), &to_f64(&[y1, y2, y3]), color,
+                                        BlendMode::Alpha),
+                DrawCommand::Sprite { x, y, color, blend, .. } =>
+                    self.filled_circle(x as i32, y as i32, 3, color, blend)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static WHITE : Rgba = (255, 255, 255, 255);
+
+    // pixels different from the black background, row by row
+    fn lit(fb: &Framebuffer) -> Vec<(u32, u32)> {
+        let mut points = vec![];
+        for y in 0..fb.height() {
+            for x in 0..fb.width() {
+                if fb.get_pixel(x, y) != (0, 0, 0, 0) {
+                    points.push((x, y));
+                }
+            }
+        }
+        points
+    }
+
+    fn rasterize(list: &DrawList) -> Framebuffer {
+        let mut fb = Framebuffer::new(10, 10);
+        fb.render(list);
+        fb
+    }
+
+    #[test]
+    fn filled_circle() {
+        let mut list = DrawList::new();
+        list.filled_circle(5, 5, 2, WHITE);
+        let fb = rasterize(&list);
+        assert_eq!(lit(&fb), vec![(5, 3),
+                                  (4, 4), (5, 4), (6, 4),
+                                  (3, 5), (4, 5), (5, 5), (6, 5), (7, 5),
+                                  (4, 6), (5, 6), (6, 6),
+                                  (5, 7)]);
+        assert_eq!(fb.get_pixel(5, 5), WHITE);
+    }
+
+    #[test]
+    fn line() {
+        let mut list = DrawList::new();
+        list.line(0, 0, 4, 2, WHITE);
+        assert_eq!(lit(&rasterize(&list)), vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn rectangle() {
+        let mut list = DrawList::new();
+        list.rectangle(1, 1, 4, 3, WHITE);
+        assert_eq!(lit(&rasterize(&list)), vec![(1, 1), (2, 1), (3, 1), (4, 1),
+                                                (1, 2), (4, 2),
+                                                (1, 3), (2, 3), (3, 3), (4, 3)]);
+    }
+
+    #[test]
+    fn filled_rectangle() {
+        let mut list = DrawList::new();
+        list.filled_rectangle(2, 1, 3, 2, WHITE);
+        assert_eq!(lit(&rasterize(&list)), vec![(2, 1), (3, 1), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn clipped_outside_of_the_buffer() {
+        let mut list = DrawList::new();
+        list.filled_circle(0, 0, 1, WHITE);
+        assert_eq!(lit(&rasterize(&list)), vec![(0, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn blend_modes() {
+        assert_eq!(blend_pixel(BlendMode::Alpha, (10, 20, 30), (200, 100, 0, 255)), (200, 100, 0));
+        assert_eq!(blend_pixel(BlendMode::Alpha, (0, 0, 0), (255, 255, 255, 0)), (0, 0, 0));
+        assert_eq!(blend_pixel(BlendMode::Alpha, (0, 100, 255), (255, 200, 0, 51)), (51, 120, 204));
+        assert_eq!(blend_pixel(BlendMode::Additive, (100, 200, 50), (100, 100, 100, 255)), (200, 255, 150));
+        assert_eq!(blend_pixel(BlendMode::Multiply, (255, 128, 0), (128, 128, 128, 255)), (128, 64, 0));
+        assert_eq!(blend_pixel(BlendMode::Screen, (0, 128, 255), (128, 128, 128, 255)), (128, 192, 255));
+    }
+
+    #[test]
+    fn blend_mode_of_the_commands() {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.clear((100, 100, 100, 255));
+        let mut list = DrawList::new();
+        list.push(DrawCommand::Pixel { x: 0, y: 0, color: (100, 200, 0, 255), blend: BlendMode::Additive });
+        list.push(DrawCommand::Pixel { x: 1, y: 0, color: (0, 0, 0, 255), blend: BlendMode::Screen });
+        fb.render(&list);
+        assert_eq!(fb.get_pixel(0, 0), (200, 255, 100, 255));
+        assert_eq!(fb.get_pixel(1, 0), (100, 100, 100, 255));
+    }
+
+    #[test]
+    fn blend_mode_of_the_wide_shapes() {
+        let mut fb = Framebuffer::new(10, 10);
+        fb.clear((100, 100, 100, 255));
+        let mut list = DrawList::new();
+        list.push(DrawCommand::ThickLine { x1: 1, y1: 2, x2: 8, y2: 2, width: 3, color: (100, 0, 0, 255),
+                                           blend: BlendMode::Additive });
+        list.push(DrawCommand::FilledPolygon { xs: vec![1, 8, 8, 1], ys: vec![6, 6, 8, 8],
+                                               color: (0, 100, 0, 255), blend: BlendMode::Additive });
+        fb.render(&list);
+        assert_eq!(fb.get_pixel(4, 2), (200, 100, 100, 255));
+        assert_eq!(fb.get_pixel(4, 7), (100, 200, 100, 255));
+        assert_eq!(fb.get_pixel(4, 4), (100, 100, 100, 255));
+    }
+}
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use sdl2::image::LoadSurface;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::surface::Surface;
+
+// Images used by the particles in `RenderMode::Sprite`, loaded through
+// sdl2::image (PNG or JPG)
+pub struct Sprites {
+    surfaces: Vec<Surface<'static>>
+}
+
+impl Sprites {
+    pub fn load(paths: &[PathBuf]) -> Result<Sprites, String> {
+        let mut surfaces = vec![];
+        for p in paths {
+            surfaces.push(Surface::from_file(p).map_err(|e| format!("{}: {}", p.display(), e))?);
+        }
+        Ok(Sprites {
+            surfaces: surfaces
+        })
+    }
+
+    pub fn textures<'r, T>(&self, tc: &'r TextureCreator<T>) -> Vec<Texture<'r>> {
+        self.surfaces.iter()
+            .map(|s| tc.create_texture_from_surface(s).unwrap())
+            .collect()
+    }
+}
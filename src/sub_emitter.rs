@@ -0,0 +1,65 @@
+use particle::Particle;
+use vec3::Vec3;
+
+use std::f64::consts::PI;
+
+use rand::{thread_rng, Rng};
+
+// Spawn `count` copies of `template` at the position of a particle when it
+// dies (and/or collides, the parent is then killed), in random directions
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SubEmitter {
+    pub template: Particle,
+    pub count: u32,
+    pub speed: f64,
+    pub inherit_velocity: bool,
+    pub inherit_color: bool,
+    pub on_death: bool,
+    pub on_collision: bool
+}
+
+impl SubEmitter {
+    pub fn new(template: Particle, count: u32, speed: f64) -> SubEmitter {
+        SubEmitter {
+            template: template,
+            count: count,
+            speed: speed,
+            inherit_velocity: false,
+            inherit_color: false,
+            on_death: true,
+            on_collision: false
+        }
+    }
+    pub fn inherit_velocity(mut self) -> SubEmitter {
+        self.inherit_velocity = true;
+        self
+    }
+    pub fn inherit_color(mut self) -> SubEmitter {
+        self.inherit_color = true;
+        self
+    }
+    pub fn on_collision(mut self) -> SubEmitter {
+        self.on_collision = true;
+        self
+    }
+
+    pub fn children(&self, parent: &Particle) -> Vec<Particle> {
+        let mut rng = thread_rng();
+        (0..self.count).map(|_| {
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            // the range must not be empty, whatever the sign of the speed
+            let speed = self.speed * rng.gen_range(0.5, 1.0);
+            let mut dir = Vec3::new(angle.cos() * speed, angle.sin() * speed, 0.0);
+            if self.inherit_velocity {
+                dir += parent.get_direction();
+            }
+            let mut child = self.template;
+            child.set_position(parent.get_position());
+            child.apply_force(dir - child.get_direction());
+            if self.inherit_color {
+                child.set_color(parent.get_color());
+            }
+            child
+        }).collect()
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// frames used to average the measured frame rate
+static FPS_WINDOW : usize = 60;
+// past this number of late ticks in one frame the simulation slows down
+// instead of trying to catch up forever
+static MAX_TICKS_BY_FRAME : u32 = 5;
+
+fn from_seconds(s: f64) -> Duration {
+    Duration::new(s.trunc() as u64, (s.fract() * 1_000_000_000.0) as u32)
+}
+
+fn seconds(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+// Frame limiter and clock of the simulation. The frames last at least
+// 1 / target fps (no limit when there is no target), and the simulation runs
+// at a fixed number of ticks by second whatever the frame rate. main does not
+// use the clock while recording, every frame then runs the same number of ticks.
+pub struct FrameTimer {
+    target: Option<Duration>,
+    tick: Duration,
+    frame_start: Instant,
+    accumulator: Duration, // real time not simulated yet
+    frames: VecDeque<Duration>
+}
+
+impl FrameTimer {
+    pub fn new(target_fps: Option<f64>, tick_rate: f64) -> FrameTimer {
+        FrameTimer {
+            target: target_fps.map(|fps| from_seconds(1.0 / fps)),
+            tick: from_seconds(1.0 / tick_rate),
+            frame_start: Instant::now(),
+            accumulator: Duration::new(0, 0),
+            frames: VecDeque::new()
+        }
+    }
+
+    // Number of ticks to run in this frame to follow the real time
+    pub fn ticks_due(&mut self) -> u32 {
+        let mut n = 0;
+        while self.accumulator >= self.tick && n < MAX_TICKS_BY_FRAME {
+            self.accumulator -= self.tick;
+            n += 1;
+        }
+        if n == MAX_TICKS_BY_FRAME {
+            // the late time is dropped, but not the part of a tick already elapsed
+            self.accumulator = from_seconds(seconds(self.accumulator) % seconds(self.tick));
+        }
+        n
+    }
+
+    // Wait until the frame lasted the target duration, to call after presenting it
+    pub fn end_frame(&mut self) {
+        if let Some(target) = self.target {
+            let elapsed = self.frame_start.elapsed();
+            if elapsed < target {
+                thread::sleep(target - elapsed);
+            }
+        }
+        let now = Instant::now();
+        let frame = now.duration_since(self.frame_start);
+        self.frame_start = now;
+        self.accumulator += frame;
+        if self.frames.len() >= FPS_WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    // Measured frame rate, averaged on the last frames
+    pub fn fps(&self) -> f64 {
+        let total: f64 = self.frames.iter().map(|&d| seconds(d)).sum();
+        if total > 0.0 {
+            self.frames.len() as f64 / total
+        } else {
+            0.0
+        }
+    }
+
+    // Duration of the last frame, waiting included
+    pub fn frame_time(&self) -> Duration {
+        self.frames.back().cloned().unwrap_or(Duration::new(0, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_follow_the_elapsed_time() {
+        let mut timer = FrameTimer::new(None, 10.0);
+        timer.accumulator = from_seconds(0.25);
+        assert_eq!(timer.ticks_due(), 2);
+        assert!((seconds(timer.accumulator) - 0.05).abs() < 1e-6);
+        assert_eq!(timer.ticks_due(), 0);
+    }
+
+    #[test]
+    fn late_ticks_keep_the_partial_tick() {
+        let mut timer = FrameTimer::new(None, 10.0);
+        timer.accumulator = from_seconds(1.25);
+        assert_eq!(timer.ticks_due(), MAX_TICKS_BY_FRAME);
+        assert!((seconds(timer.accumulator) - 0.05).abs() < 1e-6);
+    }
+}
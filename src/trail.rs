@@ -0,0 +1,42 @@
+use blend::BlendMode;
+use point3::Point3;
+use drawable::{Drawable, DrawList, DrawCommand};
+
+use std::collections::VecDeque;
+
+// Previous positions of a particle, the oldest first
+pub struct Trail<'a> {
+    pub points: &'a VecDeque<Point3>,
+    pub color: (u8, u8, u8, u8),
+    pub width: i16, // ribbon width at the particle, 1 for a simple line
+    pub blend: BlendMode
+}
+
+impl<'a> Drawable for Trail<'a> {
+    fn draw(&self, list: &mut DrawList) {
+        let n = self.points.len();
+        if n < 2 {
+            return;
+        }
+        let (r, g, b, a) = self.color;
+        for i in 1..n {
+            let p0 = self.points[i - 1];
+            let p1 = self.points[i];
+            // fade and narrow toward the oldest position
+            let f = i as f64 / n as f64;
+            let alpha = (a as f64 * f) as u8;
+            let width = ((self.width as f64) * f).max(1.0) as u8;
+            if width <= 1 {
+                list.push(DrawCommand::AaLine {
+                    x1: p0.x as i16, y1: p0.y as i16, x2: p1.x as i16, y2: p1.y as i16,
+                    color: (r, g, b, alpha), blend: self.blend
+                });
+            } else {
+                list.push(DrawCommand::ThickLine {
+                    x1: p0.x as i16, y1: p0.y as i16, x2: p1.x as i16, y2: p1.y as i16, width: width,
+                    color: (r, g, b, alpha), blend: self.blend
+                });
+            }
+        }
+    }
+}
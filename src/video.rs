@@ -0,0 +1,62 @@
+use std::io::{self, Write};
+use std::process::{Child, Command, Stdio};
+
+// Raw RGBA frames (width * height * 4 bytes each, rows from the top) written
+// to stdout or to the standard input of an encoder, for example:
+//   ffmpeg -f rawvideo -pix_fmt rgba -s {width}x{height} -r {fps} -i - out.mp4
+// {width}, {height} and {fps} are replaced in the command before running it
+// through `sh -c`.
+pub struct VideoSink {
+    out: Box<Write>,
+    encoder: Option<Child>,
+    frame_size: usize
+}
+
+impl VideoSink {
+    pub fn stdout(width: u32, height: u32) -> VideoSink {
+        VideoSink {
+            out: Box::new(io::stdout()),
+            encoder: None,
+            frame_size: (width * height * 4) as usize
+        }
+    }
+
+    pub fn spawn(command: &str, width: u32, height: u32, fps: f64) -> Result<VideoSink, String> {
+        let command = command.replace("{width}", &width.to_string())
+            .replace("{height}", &height.to_string())
+            .replace("{fps}", &fps.to_string());
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", command, e))?;
+        let stdin = child.stdin.take().unwrap();
+        Ok(VideoSink {
+            out: Box::new(stdin),
+            encoder: Some(child),
+            frame_size: (width * height * 4) as usize
+        })
+    }
+
+    pub fn write_frame(&mut self, rgba: &[u8]) -> Result<(), String> {
+        if rgba.len() != self.frame_size {
+            return Err(format!("video frame of {} bytes, expected {}", rgba.len(), self.frame_size));
+        }
+        self.out.write_all(rgba).map_err(|e| format!("video output: {}", e))
+    }
+
+    // Close the stream and wait for the encoder to write its file
+    pub fn finish(mut self) -> Result<(), String> {
+        self.out.flush().map_err(|e| format!("video output: {}", e))?;
+        // dropping stdin sends EOF to the encoder
+        self.out = Box::new(io::sink());
+        if let Some(mut child) = self.encoder.take() {
+            let status = child.wait().map_err(|e| format!("video encoder: {}", e))?;
+            if !status.success() {
+                return Err(format!("video encoder exited with {}", status));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,22 +1,76 @@
-use particle::Particle;
+use particle::{Particle, ParticleBuilder, ParticleHandle};
+use collider::Collider;
+use constraint::{self, Constraint};
+use event::{ParticleEvent, Wall};
+use sub_emitter::SubEmitter;
 use physic_property::PhysicProperty;
 use point3::Point3;
 use rectangle::Rectangle;
+use scene::Scene;
+use drawable::{Drawable, DrawList};
+use trail::Trail;
 use vec3::Vec3;
 
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
 use rayon::prelude::*;
 use rayon::iter::IntoParallelRefMutIterator;
 
+// Oldest events are dropped past this number if they are not drained often enough
+static MAX_PENDING_EVENTS : usize = 100000;
+// radius of the particles of the ropes and springs of a scene
+static LINK_PARTICLE_RADIUS : i16 = 3;
+
+pub fn millis(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1000.0 + d.subsec_nanos() as f64 / 1_000_000.0
+}
+
+// Measures of the last update, for the HUD
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct WorldStats {
+    pub alive: usize,
+    pub spawned_last_tick: usize, // particles added between the two last updates
+    pub tick_ms: f64, // whole update
+    pub physics_ms: f64, // properties, bounds and colliders on all the particles
+    // time spent in each property, summed on all the threads. Only measured
+    // when `World::profile_properties` is set, timing every call is not free.
+    pub property_ms: Vec<f64>
+}
+
 pub struct World {
     pub particles: Vec<Particle>,
     pub properties: Vec<Box<PhysicProperty>>,
     pub boundaries: Rectangle,
+    pub colliders: Vec<Collider>,
+    pub constraints: Vec<Constraint>,
+    pub solver_iterations: u32,
+    pub sub_emitters: Vec<SubEmitter>,
+    pub profile_properties: bool,
+    // keep the change of direction given by the properties, for the debug view
+    pub record_forces: bool,
+    forces: HashMap<ParticleHandle, Vec3>, // of the last update, when `record_forces` is set
+    properties_version: u64, // changed with the properties or the bounds
+    trails: HashMap<ParticleHandle, VecDeque<Point3>>,
+    scene_particles: Vec<ParticleHandle>, // ropes and springs of the loaded scene
 
     particle_creation_point: Point3,
+    spawn_template: Option<Particle>, // replaces the particles of `create_particle_fun`
     create_particle_fun: Box<Fn(Point3) -> Particle + Sync + Send>,
 
     cpt: i16,
-    iter: usize
+    iter: usize,
+    time: f64,
+    next_id: u64,
+    index: HashMap<ParticleHandle, usize>, // position of the particle in `particles`
+
+    events: VecDeque<ParticleEvent>,
+    draining: bool, // `drain_events` was called, the events are kept until then
+    subscribers: Vec<Box<FnMut(&ParticleEvent) + Send>>,
+    contacts: HashSet<(ParticleHandle, usize)>, // particle and collider touching at the last tick
+    stats: WorldStats,
+    spawned: usize // since the end of the previous update
 
 }
 
@@ -26,27 +80,94 @@ impl World {
             particles: vec![],
             properties: pr,
             boundaries: b,
+            colliders: vec![],
+            constraints: vec![],
+            solver_iterations: 4,
+            sub_emitters: vec![],
+            profile_properties: false,
+            record_forces: false,
+            forces: HashMap::new(),
+            properties_version: 0,
+            trails: HashMap::new(),
+            scene_particles: vec![],
             particle_creation_point: c,
+            spawn_template: None,
             create_particle_fun: f,
             cpt: 0,
-            iter: 0
+            iter: 0,
+            time: 0.0,
+            next_id: 1,
+            index: HashMap::new(),
+            events: VecDeque::new(),
+            draining: false,
+            subscribers: vec![],
+            contacts: HashSet::new(),
+            stats: WorldStats::default(),
+            spawned: 0
         }
     }
     pub fn update(&mut self) {
+        let tick_start = Instant::now();
+        // nobody reads the queue, only the subscribers see the events
+        if !self.draining {
+            self.events.clear();
+        }
         // let mut cpt = 0;
+        constraint::apply_springs(&self.constraints, &mut self.particles, &self.index);
         // par_iter_mut
         let prop = &self.properties;
         let bound = &self.boundaries;
+        let colliders = &self.colliders;
+        let time = self.time;
+        let profile = self.profile_properties;
+        let record = self.record_forces;
+        let costs: Vec<AtomicUsize> = prop.iter().map(|_| AtomicUsize::new(0)).collect();
+        let physics_start = Instant::now();
         // let iter = self.iter;
         // FIXME maybe useless to parallelise
         // FIXME or maybe do all physic computation (instead of just one per frame)
-        self.particles.par_iter_mut().for_each(|p: &mut Particle| {
+        let results: Vec<(Vec<ParticleEvent>, Option<(ParticleHandle, Vec3)>)> =
+            self.particles.par_iter_mut().map(|p: &mut Particle| {
+            let mut events = vec![];
+            let was_alive = p.is_alive();
+            let start = p.get_position();
+            let start_direction = p.get_direction();
             // *p = prop[iter].update_particle(p);
             // for prop in &self.properties {
-            for prop in prop.iter() {
-                *p = prop.update_particle(p);
+            for (i, prop) in prop.iter().enumerate() {
+                if profile {
+                    let start = Instant::now();
+                    *p = prop.update_particle(p, time);
+                    let d = start.elapsed();
+                    costs[i].fetch_add(d.as_secs() as usize * 1_000_000_000 + d.subsec_nanos() as usize,
+                                       Ordering::Relaxed);
+                } else {
+                    *p = prop.update_particle(p, time);
+                }
             }
+            let handle = p.handle();
+            let force = if record {
+                let d = p.get_direction();
+                Some((handle, Vec3::new(d.x - start_direction.x, d.y - start_direction.y, d.z - start_direction.z)))
+            } else {
+                None
+            };
             p.update();
+            if was_alive && !p.is_alive() {
+                events.push(ParticleEvent::Death { particle: handle, position: p.get_position() });
+            }
+            if !p.is_alive() {
+                return (events, force);
+            }
+            for (i, prop) in prop.iter().enumerate() {
+                if !prop.contains(&start) && prop.contains(&p.get_position()) {
+                    events.push(ParticleEvent::EnterProperty {
+                        particle: handle,
+                        position: p.get_position(),
+                        property: i
+                    });
+                }
+            }
             let pos = p.get_position();
             let dir = p.get_direction();
             let rad = p.get_radius() as f64;
@@ -62,6 +183,7 @@ impl World {
                                             dir.z));
                 }
                 p.set_position(Point3::new(corner.x + rad, y, z));
+                events.push(ParticleEvent::Boundary { particle: handle, position: pos, wall: Wall::Left });
             } else if (corner.x + bound.width) < (x + (rad)) && (dir.x > 0.0) {
                 if dir.x >= 0.0 {
                     p.apply_force(Vec3::new(-1.0 * dir.x * 2.0,
@@ -69,6 +191,7 @@ impl World {
                                             dir.z))
                 }
                 p.set_position(Point3::new(corner.x + bound.width - rad, y, z));
+                events.push(ParticleEvent::Boundary { particle: handle, position: pos, wall: Wall::Right });
             }
             if (y - (rad)) < corner.y {
                 if dir.y <= 0.0 {
@@ -77,6 +200,7 @@ impl World {
                                             dir.z));
                 }
                 p.set_position(Point3::new(x, corner.y + rad, z));
+                events.push(ParticleEvent::Boundary { particle: handle, position: pos, wall: Wall::Top });
             } else if (corner.y + bound.height) < (y + (rad)) {
                 if dir.y >= 0.0 {
                     p.apply_force(Vec3::new(dir.x,
@@ -84,20 +208,415 @@ impl World {
                                             dir.z));
                 }
                 p.set_position(Point3::new(x, corner.y + bound.height - rad, z));
+                events.push(ParticleEvent::Boundary { particle: handle, position: pos, wall: Wall::Bottom });
             }
-        });
+            for (i, c) in colliders.iter().enumerate() {
+                if c.collide(p) {
+                    events.push(ParticleEvent::Collision { particle: handle, position: p.get_position(), collider: i });
+                }
+            }
+            (events, force)
+        }).collect();
+        let mut events = vec![];
+        self.forces.clear();
+        for (e, force) in results {
+            events.extend(e);
+            if let Some((h, f)) = force {
+                self.forces.insert(h, f);
+            }
+        }
+        let physics_ms = millis(physics_start.elapsed());
+        let property_ms = if profile {
+            costs.iter().map(|c| c.load(Ordering::Relaxed) as f64 / 1_000_000.0).collect()
+        } else {
+            vec![]
+        };
+        let events = self.contact_begins(events);
+        let triggered = self.triggered_sub_emitters(&events);
+        for e in events {
+            self.emit(e);
+        }
+        for (parent, collided) in triggered {
+            if collided {
+                if let Some(i) = self.index_of(parent.handle()) {
+                    self.particles[i].kill();
+                }
+                self.emit(ParticleEvent::Death { particle: parent.handle(), position: parent.get_position() });
+            }
+            self.spawn_children(&parent);
+        }
+        constraint::solve(&self.constraints, &mut self.particles, &self.index, self.solver_iterations);
+        self.update_trails();
+        self.time += 1.0;
         self.cpt += 1;
         if self.cpt > 100 {
-            self.particles.retain(|&x| x.is_alive());
+            self.compact();
             self.cpt = 0;
         }
+        self.stats.spawned_last_tick = self.spawned;
+        self.spawned = 0;
+        self.stats.tick_ms = millis(tick_start.elapsed());
+        self.stats.physics_ms = physics_ms;
+        self.stats.property_ms = property_ms;
         // self.iter += 1;
         // if self.iter >= self.properties.len() {
         //     self.iter = 0;
         // }
     }
 
-    pub fn create_particle(&mut self) {
-        self.particles.push((self.create_particle_fun)(self.particle_creation_point).clone());
+    // Replace the properties, bounds, colliders, emitter, ropes, springs and
+    // sub emitters, particles still inside the new bounds are kept
+    pub fn load_scene(&mut self, scene: Scene) {
+        self.properties = scene.properties;
+        self.colliders = scene.colliders;
+        self.sub_emitters = scene.sub_emitters;
+        self.spawn_template = scene.spawn;
+        // the collider indices changed
+        self.contacts.clear();
+        self.particle_creation_point = scene.emitter;
+        self.remove_scene_particles();
+        // they live until the next scene
+        let template = ParticleBuilder::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0))
+            .with_radius(LINK_PARTICLE_RADIUS)
+            .with_lifetime(::std::u64::MAX)
+            .with_color(200, 200, 200)
+            .create();
+        for r in scene.ropes {
+            let ids = self.add_rope(template, r.start, r.end, r.segments, r.stiffness, r.pinned);
+            self.scene_particles.extend(ids);
+        }
+        for s in scene.springs {
+            let (a, b) = self.add_spring(template, s.a, s.b, s.stiffness, s.damping, s.pinned);
+            self.scene_particles.push(a);
+            self.scene_particles.push(b);
+        }
+        self.set_bounds(scene.boundaries);
+    }
+
+    // Kill the particles of the ropes and springs of the previous scene
+    fn remove_scene_particles(&mut self) {
+        let removed: HashSet<ParticleHandle> = self.scene_particles.drain(..).collect();
+        for &h in &removed {
+            let dead = match self.get_mut(h) {
+                Some(p) => {
+                    p.kill();
+                    *p
+                }
+                None => continue
+            };
+            self.emit(ParticleEvent::Death { particle: h, position: dead.get_position() });
+        }
+        self.constraints.retain(|c| match *c {
+            Constraint::Distance { a, b, .. } | Constraint::Spring { a, b, .. } =>
+                !removed.contains(&a) && !removed.contains(&b),
+            Constraint::Pin { a, .. } => !removed.contains(&a)
+        });
+    }
+
+    // Particles outside of the new bounds are removed
+    pub fn set_bounds(&mut self, b: Rectangle) {
+        self.boundaries = b;
+        // also reached by `load_scene`, with the new properties
+        self.properties_changed();
+        let mut outside = vec![];
+        for p in self.particles.iter_mut() {
+            if p.is_alive() && !b.is_in_bound(&p.get_position()) {
+                p.kill();
+                outside.push(ParticleEvent::Death { particle: p.handle(), position: p.get_position() });
+            }
+        }
+        for e in outside {
+            self.emit(e);
+        }
+        self.compact();
+    }
+
+    // Remove the dead particles and everything referring to them
+    fn compact(&mut self) {
+        self.particles.retain(|&x| x.is_alive());
+        self.rebuild_index();
+        let index = &self.index;
+        self.trails.retain(|h, _| index.contains_key(h));
+        self.scene_particles.retain(|h| index.contains_key(h));
+        self.constraints.retain(|c| match *c {
+            Constraint::Distance { a, b, .. } | Constraint::Spring { a, b, .. } =>
+                index.contains_key(&a) && index.contains_key(&b),
+            Constraint::Pin { a, .. } => index.contains_key(&a)
+        });
+        self.stats.alive = self.particles.len();
+    }
+
+    // Keep the collisions only when the contact begins, not while it lasts
+    fn contact_begins(&mut self, events: Vec<ParticleEvent>) -> Vec<ParticleEvent> {
+        let mut contacts = HashSet::new();
+        let mut begins = vec![];
+        for e in events {
+            if let ParticleEvent::Collision { particle, collider, .. } = e {
+                contacts.insert((particle, collider));
+                if self.contacts.contains(&(particle, collider)) {
+                    continue;
+                }
+            }
+            begins.push(e);
+        }
+        self.contacts = contacts;
+        begins
+    }
+
+    // Particles whose sub emitter must fire, and if it is because of a collision
+    fn triggered_sub_emitters(&self, events: &[ParticleEvent]) -> Vec<(Particle, bool)> {
+        let mut done = HashSet::new();
+        let mut triggered = vec![];
+        for e in events {
+            let (h, collided) = match *e {
+                ParticleEvent::Death { particle, .. } => (particle, false),
+                ParticleEvent::Collision { particle, .. } => (particle, true),
+                _ => continue
+            };
+            if done.contains(&h) {
+                continue;
+            }
+            let parent = match self.index_of(h) {
+                Some(i) => self.particles[i],
+                None => continue
+            };
+            if let Some(se) = parent.get_sub_emitter().and_then(|i| self.sub_emitters.get(i)) {
+                if (collided && se.on_collision) || (!collided && se.on_death) {
+                    done.insert(h);
+                    triggered.push((parent, collided));
+                }
+            }
+        }
+        triggered
+    }
+
+    fn spawn_children(&mut self, parent: &Particle) {
+        let children = match parent.get_sub_emitter().and_then(|i| self.sub_emitters.get(i)) {
+            Some(se) => se.children(parent),
+            None => return
+        };
+        for c in children {
+            self.add_particle(c);
+        }
+    }
+
+    fn update_trails(&mut self) {
+        for p in self.particles.iter() {
+            let len = p.get_trail_length() as usize;
+            if len == 0 || !p.is_alive() {
+                continue;
+            }
+            let trail = self.trails.entry(p.handle()).or_insert_with(VecDeque::new);
+            trail.push_back(p.get_position());
+            while trail.len() > len {
+                trail.pop_front();
+            }
+        }
+    }
+
+    pub fn stats(&self) -> &WorldStats {
+        &self.stats
+    }
+
+    // Change of direction given by the properties to a particle at the last
+    // update, only recorded while `record_forces` is set
+    pub fn force(&self, h: ParticleHandle) -> Option<Vec3> {
+        self.forces.get(&h).cloned()
+    }
+
+    // To call when the properties are modified from outside, lets the views
+    // know their areas may have moved
+    pub fn properties_changed(&mut self) {
+        self.properties_version += 1;
+    }
+
+    pub fn properties_version(&self) -> u64 {
+        self.properties_version
+    }
+
+    pub fn trail(&self, h: ParticleHandle) -> Option<&VecDeque<Point3>> {
+        self.trails.get(&h)
+    }
+
+    fn emit(&mut self, e: ParticleEvent) {
+        match e {
+            ParticleEvent::Spawn { .. } => {
+                self.stats.alive += 1;
+                self.spawned += 1;
+            }
+            ParticleEvent::Death { .. } => self.stats.alive = self.stats.alive.saturating_sub(1),
+            _ => {}
+        }
+        for s in self.subscribers.iter_mut() {
+            s(&e);
+        }
+        if self.events.len() >= MAX_PENDING_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(e);
+    }
+
+    // Called for each event, as soon as it happens
+    pub fn subscribe(&mut self, f: Box<FnMut(&ParticleEvent) + Send>) {
+        self.subscribers.push(f);
+    }
+
+    // Events since the last call. Until the first call the events are dropped
+    // at each tick.
+    pub fn drain_events(&mut self) -> Vec<ParticleEvent> {
+        self.draining = true;
+        self.events.drain(..).collect()
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index.clear();
+        for (i, p) in self.particles.iter().enumerate() {
+            self.index.insert(p.handle(), i);
+        }
+    }
+
+    pub fn index_of(&self, h: ParticleHandle) -> Option<usize> {
+        self.index.get(&h).cloned()
+    }
+
+    // None once the particle is dead
+    pub fn get(&self, h: ParticleHandle) -> Option<&Particle> {
+        match self.index_of(h) {
+            Some(i) if self.particles[i].is_alive() => Some(&self.particles[i]),
+            _ => None
+        }
+    }
+
+    pub fn get_mut(&mut self, h: ParticleHandle) -> Option<&mut Particle> {
+        match self.index_of(h) {
+            Some(i) if self.particles[i].is_alive() => Some(&mut self.particles[i]),
+            _ => None
+        }
+    }
+
+    // Add a particle built outside of the world (ropes, cloth, ...)
+    pub fn add_particle(&mut self, p: Particle) -> ParticleHandle {
+        let mut p = p;
+        let h = ParticleHandle(self.next_id);
+        self.next_id += 1;
+        p.set_handle(h);
+        self.index.insert(h, self.particles.len());
+        self.particles.push(p);
+        self.emit(ParticleEvent::Spawn { particle: h, position: p.get_position() });
+        h
+    }
+
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn get_creation_point(&self) -> Point3 {
+        self.particle_creation_point
+    }
+
+    // Chain of `segments` + 1 particles linked by distance constraints, the
+    // first one is pinned if `pinned`
+    pub fn add_rope(&mut self, template: Particle, start: Point3, end: Point3, segments: u32,
+                    stiffness: f64, pinned: bool) -> Vec<ParticleHandle> {
+        let segments = segments.max(1);
+        let step = Vec3::new((end.x - start.x) / segments as f64,
+                             (end.y - start.y) / segments as f64,
+                             (end.z - start.z) / segments as f64);
+        let mut ids = vec![];
+        let mut pos = start;
+        for _ in 0..(segments + 1) {
+            let mut p = template;
+            p.set_position(pos);
+            ids.push(self.add_particle(p));
+            pos.apply_vec(step);
+        }
+        for w in ids.windows(2) {
+            self.constraints.push(Constraint::Distance {
+                a: w[0],
+                b: w[1],
+                length: step.length(),
+                stiffness: stiffness
+            });
+        }
+        if pinned {
+            self.constraints.push(Constraint::Pin { a: ids[0], position: start });
+        }
+        ids
+    }
+
+    // Two particles linked by a damped spring at rest at their initial
+    // distance, the first one is pinned if `pinned`
+    pub fn add_spring(&mut self, template: Particle, a: Point3, b: Point3, stiffness: f64,
+                      damping: f64, pinned: bool) -> (ParticleHandle, ParticleHandle) {
+        let mut p = template;
+        p.set_position(a);
+        let ha = self.add_particle(p);
+        p.set_position(b);
+        let hb = self.add_particle(p);
+        self.constraints.push(Constraint::Spring {
+            a: ha,
+            b: hb,
+            rest_length: Vec3::new(b.x - a.x, b.y - a.y, b.z - a.z).length(),
+            stiffness: stiffness,
+            damping: damping
+        });
+        if pinned {
+            self.constraints.push(Constraint::Pin { a: ha, position: a });
+        }
+        (ha, hb)
+    }
+
+    pub fn create_particle(&mut self) -> ParticleHandle {
+        let mut p = (self.create_particle_fun)(self.particle_creation_point).clone();
+        if let Some(template) = self.spawn_template {
+            // only the position and the direction come from the function
+            let mut t = template;
+            t.set_position(p.get_position());
+            t.apply_force(p.get_direction() - t.get_direction());
+            p = t;
+        }
+        self.add_particle(p)
+    }
+}
+
+impl Drawable for World {
+    fn draw(&self, list: &mut DrawList) {
+        // Point where the particle are created
+        list.filled_circle(self.particle_creation_point.x as i16,
+                           self.particle_creation_point.y as i16,
+                           1,
+                           (255, 255, 255, 255));
+        for p in &self.properties {
+            if let Some(d) = p.as_drawable() {
+                d.draw(list);
+            }
+        }
+        for c in &self.colliders {
+            c.draw(list);
+        }
+        for c in &self.constraints {
+            let (a, b) = match *c {
+                Constraint::Distance { a, b, .. } | Constraint::Spring { a, b, .. } => (a, b),
+                Constraint::Pin { .. } => continue
+            };
+            if let (Some(a), Some(b)) = (self.get(a), self.get(b)) {
+                let (pa, pb) = (a.get_position(), b.get_position());
+                list.line(pa.x as i16, pa.y as i16, pb.x as i16, pb.y as i16, (200, 200, 200, 255));
+            }
+        }
+        for p in &self.particles {
+            if p.is_alive() {
+                if let Some(points) = self.trail(p.handle()) {
+                    Trail {
+                        points: points,
+                        color: p.compute_color(),
+                        width: p.get_radius(),
+                        blend: p.get_blend_mode()
+                    }.draw(list);
+                }
+                p.draw(list);
+            }
+        }
+        self.boundaries.draw(list);
     }
 }